@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use treasure_map::{
+    element::{Element, Orientation, RawAdventurer, RawMap, RawMountain, RawTreasure},
+    generator::{generate, GeneratorOptions},
+    simulation::{ConflictPolicy, ResolutionMode, Simulation},
+};
+
+fn scenario(
+    size: usize,
+    adventurers: usize,
+    pattern_length: usize,
+) -> HashMap<String, Vec<Element>> {
+    generate(&GeneratorOptions {
+        width: size,
+        height: size,
+        mountain_density: 0.05,
+        treasure_piles: size,
+        adventurers,
+        pattern_length,
+        ..GeneratorOptions::default()
+    })
+    .unwrap()
+}
+
+/// A square map of `size` cells a side with a row of treasures and a row of
+/// mountains, built by hand since generating it would visit every cell.
+/// Adventurers fill every other row from the top, heading south.
+fn sparse_scenario(
+    size: usize,
+    adventurers: usize,
+    pattern_length: usize,
+) -> HashMap<String, Vec<Element>> {
+    let pattern: String = "AADAAG".chars().cycle().take(pattern_length).collect();
+    let mut content = HashMap::new();
+    content.insert(
+        "C".to_string(),
+        vec![Element::RawMap(RawMap::new(size, size))],
+    );
+    content.insert(
+        "T".to_string(),
+        (0..size)
+            .step_by(7)
+            .map(|x| Element::RawTreasure(RawTreasure::new(x, 5, 3)))
+            .collect(),
+    );
+    content.insert(
+        "M".to_string(),
+        (0..size)
+            .step_by(11)
+            .map(|x| Element::RawMountain(RawMountain::new(x, 9)))
+            .collect(),
+    );
+    content.insert(
+        "A".to_string(),
+        (0..adventurers)
+            .map(|index| {
+                Element::RawAdventurer(RawAdventurer::new(
+                    format!("A{}", index),
+                    index % size,
+                    1 + 2 * (index / size),
+                    Orientation::South,
+                    pattern.clone(),
+                ))
+            })
+            .collect(),
+    );
+    content
+}
+
+/// A single adventurer playing a very long pattern: each instruction should
+/// cost the same however many are left.
+fn long_pattern(c: &mut Criterion) {
+    let content = scenario(64, 1, 200_000);
+
+    c.bench_function("long pattern", |b| {
+        b.iter_batched(
+            || Simulation::from_hashmap(&content),
+            |mut simulation| simulation.run(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+/// Many adventurers sharing a map, in both resolution modes.
+fn many_adventurers(c: &mut Criterion) {
+    let content = scenario(300, 5_000, 64);
+    let mut group = c.benchmark_group("many adventurers");
+
+    for (name, resolution) in [
+        ("sequential", ResolutionMode::Sequential),
+        (
+            "simultaneous",
+            ResolutionMode::Simultaneous(ConflictPolicy::PriorityByName),
+        ),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Simulation::from_hashmap(&content).with_resolution(resolution),
+                |mut simulation| simulation.run(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Loading a large map with few elements.
+fn large_map(c: &mut Criterion) {
+    let content = scenario(2_000, 100, 16);
+
+    c.bench_function("large map", |b| {
+        b.iter(|| Simulation::from_hashmap(&content))
+    });
+}
+
+/// A 10,000x10,000 map, played by 10,000 adventurers or by a single one
+/// with a million instructions.
+fn requested_scale(c: &mut Criterion) {
+    let mut group = c.benchmark_group("requested scale");
+
+    for (name, adventurers, pattern_length) in [
+        ("many adventurers", 10_000, 100),
+        ("million instructions", 1, 1_000_000),
+    ] {
+        let content = sparse_scenario(10_000, adventurers, pattern_length);
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Simulation::from_hashmap(&content),
+                |mut simulation| simulation.run(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = long_pattern, many_adventurers, large_map, requested_scale
+}
+criterion_main!(benches);
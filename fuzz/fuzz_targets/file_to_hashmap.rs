@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use treasure_map::file::read_scenario;
+
+// The parser must return an error on malformed input, never panic.
+fuzz_target!(|data: &[u8]| {
+    let _ = read_scenario(data);
+});
@@ -0,0 +1,374 @@
+use std::{
+    fmt, fs,
+    fs::File,
+    io::{self, BufReader},
+    panic,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    file::{read_scenario, write_result},
+    simulation::{ResolutionMode, Simulation},
+    validation::{self, ValidationError},
+};
+
+/// Suffix of the result written next to a scenario, which is also how a
+/// directory listing tells results and scenarios apart.
+pub static RESULT_SUFFIX: &str = ".result.txt";
+
+pub struct BatchOptions {
+    /// Number of scenarios run at the same time.
+    pub jobs: usize,
+    /// Directory receiving the results, laid out like the inputs below the
+    /// batch root. Results are written next to their input without it.
+    pub output_dir: Option<PathBuf>,
+    pub resolution: ResolutionMode,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+            output_dir: None,
+            resolution: ResolutionMode::Sequential,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BatchError {
+    Io(io::Error),
+    Invalid(Vec<ValidationError>),
+    Panic(String),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchError::Io(error) => write!(f, "{}", error),
+            BatchError::Invalid(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(ValidationError::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            BatchError::Panic(message) => write!(f, "simulation failed : {}", message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BatchSuccess {
+    pub output: PathBuf,
+    pub score: usize,
+    pub turns: usize,
+}
+
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub input: PathBuf,
+    pub result: Result<BatchSuccess, BatchError>,
+}
+
+/// Scenario files of a batch: the scenarios of a directory, or the files
+/// matching a glob where `*` and `?` may appear in any path component.
+/// Results of an earlier batch are left out. Returns the batch root, below
+/// which results are laid out in an output directory, with the sorted files.
+pub fn collect_inputs(pattern: &str) -> io::Result<(PathBuf, Vec<PathBuf>)> {
+    let path = Path::new(pattern);
+    if path.is_dir() {
+        let mut inputs: Vec<PathBuf> = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?;
+        inputs.retain(|input| input.is_file() && is_scenario(input));
+        inputs.sort();
+
+        return Ok((path.to_path_buf(), inputs));
+    }
+
+    let components: Vec<String> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let literal = components
+        .iter()
+        .take_while(|component| !component.contains(['*', '?']))
+        .count();
+    let root: PathBuf = components[..literal].iter().collect();
+
+    let mut inputs = Vec::new();
+    expand(&root, &components[literal..], &mut inputs)?;
+    inputs.retain(|input| is_scenario(input));
+    inputs.sort();
+
+    Ok((root, inputs))
+}
+
+fn expand(base: &Path, components: &[String], inputs: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((component, rest)) = components.split_first() else {
+        if base.is_file() {
+            inputs.push(base.to_path_buf());
+        }
+        return Ok(());
+    };
+
+    let directory = if base.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base
+    };
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if matches_glob(component, &name) {
+            expand(&base.join(&name), rest, inputs)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Matches a file name against a pattern where `*` stands for any sequence
+/// of characters and `?` for any single character.
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // matched[j] : whether the pattern read so far matches name[..j]
+    let mut matched = vec![false; name.len() + 1];
+    matched[0] = true;
+
+    for token in pattern.iter() {
+        let mut next = vec![false; name.len() + 1];
+        for index in 0..=name.len() {
+            next[index] = match token {
+                '*' => matched[index] || (index > 0 && next[index - 1]),
+                '?' => index > 0 && matched[index - 1],
+                _ => index > 0 && matched[index - 1] && name[index - 1] == *token,
+            };
+        }
+        matched = next;
+    }
+
+    matched[name.len()]
+}
+
+fn is_scenario(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+
+    name.ends_with(".txt") && !name.ends_with(RESULT_SUFFIX)
+}
+
+/// Where the result of `input` is written.
+pub fn output_path(input: &Path, root: &Path, options: &BatchOptions) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let file_name = format!("{}{}", stem, RESULT_SUFFIX);
+
+    match &options.output_dir {
+        Some(output_dir) => {
+            let relative = input.strip_prefix(root).unwrap_or(input);
+            output_dir.join(relative).with_file_name(file_name)
+        }
+        None => input.with_file_name(file_name),
+    }
+}
+
+/// Runs every scenario on `options.jobs` threads and returns the outcomes in
+/// the order of `inputs`. A scenario that cannot be read, is invalid or makes
+/// the simulation panic is reported as failed without stopping the others.
+pub fn run_batch(root: &Path, inputs: &[PathBuf], options: &BatchOptions) -> Vec<BatchOutcome> {
+    let next = AtomicUsize::new(0);
+    let outcomes: Mutex<Vec<Option<BatchOutcome>>> =
+        Mutex::new((0..inputs.len()).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.clamp(1, inputs.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(input) = inputs.get(index) else {
+                    break;
+                };
+
+                let outcome = BatchOutcome {
+                    input: input.clone(),
+                    result: run_one(input, &output_path(input, root, options), options),
+                };
+                outcomes.lock().unwrap()[index] = Some(outcome);
+            });
+        }
+    });
+
+    outcomes
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|outcome| outcome.expect("Every scenario has been run"))
+        .collect()
+}
+
+fn run_one(
+    input: &Path,
+    output: &Path,
+    options: &BatchOptions,
+) -> Result<BatchSuccess, BatchError> {
+    let content = read_scenario(BufReader::new(File::open(input).map_err(BatchError::Io)?))
+        .map_err(BatchError::Io)?;
+
+    let errors = validation::validate(&content);
+    if !errors.is_empty() {
+        return Err(BatchError::Invalid(errors));
+    }
+
+    let simulation = panic::catch_unwind(|| {
+        let mut simulation = Simulation::from_hashmap(&content).with_resolution(options.resolution);
+        simulation.run();
+        simulation
+    })
+    .map_err(|payload| {
+        let message = payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| {
+                payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+            })
+            .unwrap_or_default();
+        BatchError::Panic(message)
+    })?;
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(BatchError::Io)?;
+    }
+    write_result(
+        &simulation.to_hashmap(),
+        &mut File::create(output).map_err(BatchError::Io)?,
+    )
+    .map_err(BatchError::Io)?;
+
+    Ok(BatchSuccess {
+        output: output.to_path_buf(),
+        score: simulation.score(),
+        turns: simulation.turn,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use parameterized::parameterized;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    fn init_mock_batch() -> io::Result<TempDir> {
+        let root = TempDir::new()?;
+        fs::create_dir(root.path().join("easy"))?;
+        fs::create_dir(root.path().join("broken"))?;
+        fs::write(
+            root.path().join("easy/input.txt"),
+            "C - 3 - 4\nT - 0 - 1 - 2\nA - Lara - 0 - 0 - S - A\n",
+        )?;
+        fs::write(
+            root.path().join("broken/input.txt"),
+            "C - 3 - 4\nM - 5 - 5\nA - Lara - 0 - 0 - S - A\n",
+        )?;
+        fs::write(root.path().join("easy/notes.md"), "not a scenario")?;
+
+        Ok(root)
+    }
+
+    /* SHOULD MATCH GLOB */
+
+    #[parameterized(
+        pattern = { "*.txt", "input.txt", "in?ut.txt", "*", "*.txt", "a*b*c" },
+        name = { "input.txt", "input.txt", "input.txt", "", "input.md", "abbc" },
+        expected = { true, true, true, true, false, true })]
+    fn should_match_glob(pattern: &str, name: &str, expected: bool) {
+        assert_eq!(matches_glob(pattern, name), expected);
+    }
+
+    /* SHOULD COLLECT INPUTS */
+
+    #[test]
+    fn should_collect_scenarios_matching_glob_in_sub_directories() -> io::Result<()> {
+        // Given
+        let root = init_mock_batch()?;
+        fs::write(root.path().join("easy/input.result.txt"), "")?;
+        let pattern = format!("{}/*/*.txt", root.path().display());
+
+        // When
+        let (batch_root, inputs) = collect_inputs(&pattern)?;
+
+        // Then
+        assert_eq!(batch_root, root.path());
+        assert_eq!(
+            inputs,
+            vec![
+                root.path().join("broken/input.txt"),
+                root.path().join("easy/input.txt")
+            ]
+        );
+
+        Ok(())
+    }
+
+    /* SHOULD RUN BATCH */
+
+    #[test]
+    fn should_run_every_scenario_and_report_failures() -> io::Result<()> {
+        // Given
+        let root = init_mock_batch()?;
+        let output_dir = TempDir::new()?;
+        let (batch_root, inputs) =
+            collect_inputs(&format!("{}/*/input.txt", root.path().display()))?;
+        let options = BatchOptions {
+            jobs: 2,
+            output_dir: Some(output_dir.path().to_path_buf()),
+            ..BatchOptions::default()
+        };
+
+        // When
+        let outcomes = run_batch(&batch_root, &inputs, &options);
+
+        // Then
+        assert!(matches!(
+            outcomes[0].result,
+            Err(BatchError::Invalid(ref errors)) if errors.len() == 1
+        ));
+        let success = outcomes[1].result.as_ref().unwrap();
+        assert_eq!(
+            success.output,
+            output_dir.path().join("easy/input.result.txt")
+        );
+        assert_eq!(success.score, 1);
+        assert_eq!(
+            fs::read_to_string(&success.output)?,
+            "C - 3 - 4\nT - 0 - 1 - 1\nA - Lara - 0 - 1 - S - 1\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_write_result_next_to_input_by_default() {
+        let options = BatchOptions::default();
+
+        assert_eq!(
+            output_path(Path::new("maps/easy.txt"), Path::new("maps"), &options),
+            PathBuf::from("maps/easy.result.txt")
+        );
+    }
+}
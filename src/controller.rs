@@ -0,0 +1,556 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+};
+
+use crate::{
+    element::Orientation,
+    grid::Geometry,
+    observation::{CellView, Observation},
+    rng::Rng,
+    script::{Script, SCRIPT_PREFIX},
+};
+
+/// What an adventurer does on its turn.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Instruction {
+    Advance,
+    TurnLeft,
+    TurnRight,
+    Wait,
+}
+
+impl Instruction {
+    /// Instruction of a pattern letter. Any letter other than `A`, `G` and
+    /// `D` is played as a wait.
+    pub fn from_char(raw_value: char) -> Instruction {
+        match raw_value {
+            'A' => Instruction::Advance,
+            'G' => Instruction::TurnLeft,
+            'D' => Instruction::TurnRight,
+            _ => Instruction::Wait,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Instruction::Advance => 'A',
+            Instruction::TurnLeft => 'G',
+            Instruction::TurnRight => 'D',
+            Instruction::Wait => 'W',
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "A" => Ok(Instruction::Advance),
+            "G" => Ok(Instruction::TurnLeft),
+            "D" => Ok(Instruction::TurnRight),
+            "W" => Ok(Instruction::Wait),
+            _ => Err(format!("Unknown instruction : {}", raw_value)),
+        }
+    }
+}
+
+/// Chooses the instructions of an adventurer, one each time it acts, from
+/// what it sees.
+pub trait Controller {
+    fn decide(&mut self, observation: &Observation) -> Instruction;
+
+    /// Whether `decide` looks at the observation. Controllers that do not
+    /// are given one without any cell, which is much cheaper to build.
+    fn needs_observation(&self) -> bool {
+        true
+    }
+
+    /// What the next choices depend on besides the observation: a
+    /// simulation only plays the same from two states when its controllers
+    /// return the same value in both.
+    fn state(&self) -> u64 {
+        0
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller>;
+}
+
+impl Clone for Box<dyn Controller> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Plays the pattern of its adventurer from its cursor, which the simulation
+/// moves on as instructions are played. It reads the next instruction from
+/// the observation rather than keeping a copy of the pattern.
+#[derive(Clone)]
+pub struct PatternController;
+
+impl Controller for PatternController {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        observation.next_instruction.unwrap_or(Instruction::Wait)
+    }
+
+    fn needs_observation(&self) -> bool {
+        false
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Runs a script within a budget of steps for each instruction.
+#[derive(Clone)]
+pub struct ScriptController {
+    script: Script,
+    budget: usize,
+}
+
+impl ScriptController {
+    pub fn new(script: Script, budget: usize) -> ScriptController {
+        ScriptController { script, budget }
+    }
+}
+
+impl Controller for ScriptController {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        self.script.decide(observation, self.budget)
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Advances when it can, twice as often as it turns either way. Its
+/// generator never comes back to an earlier state, so a run with a random
+/// walk only stops at the turn limit.
+#[derive(Clone)]
+pub struct RandomWalk {
+    rng: Rng,
+}
+
+impl RandomWalk {
+    pub fn new(seed: u64) -> RandomWalk {
+        RandomWalk {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Controller for RandomWalk {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        if is_free(observation, observation.orientation) {
+            *self.rng.choose(&[
+                Instruction::Advance,
+                Instruction::Advance,
+                Instruction::TurnLeft,
+                Instruction::TurnRight,
+            ])
+        } else {
+            *self
+                .rng
+                .choose(&[Instruction::TurnLeft, Instruction::TurnRight])
+        }
+    }
+
+    fn state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Heads for the nearest treasure in sight along the shortest way through
+/// free cells, and goes straight on, turning right when blocked, while it
+/// sees none.
+#[derive(Clone)]
+pub struct GreedyController;
+
+impl GreedyController {
+    /// Orientation of the first step towards the nearest treasure in sight.
+    fn first_step(observation: &Observation) -> Option<Orientation> {
+        let geometry = observation.geometry();
+        let mut first_steps: HashMap<(isize, isize), Orientation> = HashMap::new();
+        let mut visited: HashSet<(isize, isize)> = HashSet::from([(0, 0)]);
+        let mut queue = VecDeque::from([(0, 0)]);
+
+        while let Some(offset) = queue.pop_front() {
+            for orientation in geometry.orientations() {
+                let Some((dx, dy)) = geometry.offset(*orientation) else {
+                    continue;
+                };
+                let next = (offset.0 + dx, offset.1 + dy);
+                let Some(cell) = observation.at(next.0, next.1) else {
+                    continue;
+                };
+                if !cell.is_free() || !visited.insert(next) {
+                    continue;
+                }
+
+                let first_step = first_steps.get(&offset).copied().unwrap_or(*orientation);
+                if cell.treasure() > 0 {
+                    return Some(first_step);
+                }
+                first_steps.insert(next, first_step);
+                queue.push_back(next);
+            }
+        }
+
+        None
+    }
+}
+
+impl Controller for GreedyController {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        match Self::first_step(observation) {
+            Some(towards) => turn_towards(observation.geometry(), observation.orientation, towards),
+            None if is_free(observation, observation.orientation) => Instruction::Advance,
+            None => Instruction::TurnRight,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Keeps a wall on one side: turns towards that side whenever it is free,
+/// then steps forward, and turns away from it when blocked ahead.
+#[derive(Clone)]
+pub struct WallFollower {
+    right_hand: bool,
+    /// Whether the last instruction was a turn towards the wall side.
+    turned: bool,
+}
+
+impl WallFollower {
+    pub fn new(right_hand: bool) -> WallFollower {
+        WallFollower {
+            right_hand,
+            turned: false,
+        }
+    }
+}
+
+impl Controller for WallFollower {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        let (towards_wall, away_from_wall) = if self.right_hand {
+            (Instruction::TurnRight, Instruction::TurnLeft)
+        } else {
+            (Instruction::TurnLeft, Instruction::TurnRight)
+        };
+        let orientation = observation.orientation;
+        let side = observation.geometry().turn(orientation, self.right_hand);
+
+        if !self.turned && is_free(observation, side) {
+            self.turned = true;
+            return towards_wall;
+        }
+        self.turned = false;
+
+        if is_free(observation, orientation) {
+            Instruction::Advance
+        } else {
+            away_from_wall
+        }
+    }
+
+    fn state(&self) -> u64 {
+        self.turned as u64
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+/// Plays the last instruction commanded to its adventurer, which its
+/// observation carries, so that code outside the simulation can drive it
+/// turn by turn. Waits until commanded.
+#[derive(Clone)]
+pub struct ExternalController;
+
+impl Controller for ExternalController {
+    fn decide(&mut self, observation: &Observation) -> Instruction {
+        observation.command.unwrap_or(Instruction::Wait)
+    }
+
+    fn needs_observation(&self) -> bool {
+        false
+    }
+
+    fn box_clone(&self) -> Box<dyn Controller> {
+        Box::new(self.clone())
+    }
+}
+
+fn is_free(observation: &Observation, orientation: Orientation) -> bool {
+    observation
+        .towards(orientation)
+        .is_some_and(CellView::is_free)
+}
+
+/// Advance when already facing `towards`, otherwise the turn reaching it in
+/// fewer steps.
+fn turn_towards(geometry: &dyn Geometry, from: Orientation, towards: Orientation) -> Instruction {
+    let orientations = geometry.orientations();
+    let (Some(from), Some(towards)) = (
+        orientations.iter().position(|other| *other == from),
+        orientations.iter().position(|other| *other == towards),
+    ) else {
+        return Instruction::TurnRight;
+    };
+    let clockwise_steps = (towards + orientations.len() - from) % orientations.len();
+
+    match clockwise_steps {
+        0 => Instruction::Advance,
+        steps if steps * 2 <= orientations.len() => Instruction::TurnRight,
+        _ => Instruction::TurnLeft,
+    }
+}
+
+/// Which controller drives an adventurer: its pattern by default.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ControllerKind {
+    Pattern,
+    Script(Script),
+    RandomWalk(u64),
+    Greedy,
+    WallFollower { right_hand: bool },
+    External,
+}
+
+impl ControllerKind {
+    /// Controller of this kind, letting scripts take `script_budget` steps.
+    pub fn build(&self, script_budget: usize) -> Box<dyn Controller> {
+        match self {
+            ControllerKind::Pattern => Box::new(PatternController),
+            ControllerKind::Script(script) => {
+                Box::new(ScriptController::new(script.clone(), script_budget))
+            }
+            ControllerKind::RandomWalk(seed) => Box::new(RandomWalk::new(*seed)),
+            ControllerKind::Greedy => Box::new(GreedyController),
+            ControllerKind::WallFollower { right_hand } => Box::new(WallFollower::new(*right_hand)),
+            ControllerKind::External => Box::new(ExternalController),
+        }
+    }
+}
+
+impl FromStr for ControllerKind {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        if let Some(source) = raw_value.strip_prefix(SCRIPT_PREFIX) {
+            return source.parse().map(ControllerKind::Script);
+        }
+
+        match raw_value.split_once(':') {
+            None if raw_value == "pattern" => Ok(ControllerKind::Pattern),
+            None if raw_value == "random" => Ok(ControllerKind::RandomWalk(0)),
+            None if raw_value == "greedy" => Ok(ControllerKind::Greedy),
+            None if raw_value == "wall" => Ok(ControllerKind::WallFollower { right_hand: true }),
+            None if raw_value == "external" => Ok(ControllerKind::External),
+            Some(("random", seed)) => seed
+                .parse()
+                .map(ControllerKind::RandomWalk)
+                .map_err(|_| format!("Invalid seed : {}", seed)),
+            Some(("wall", "right")) => Ok(ControllerKind::WallFollower { right_hand: true }),
+            Some(("wall", "left")) => Ok(ControllerKind::WallFollower { right_hand: false }),
+            _ => Err(format!("Unknown controller : {}", raw_value)),
+        }
+    }
+}
+
+impl fmt::Display for ControllerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ControllerKind::Pattern => write!(f, "pattern"),
+            ControllerKind::Script(script) => write!(f, "{}{}", SCRIPT_PREFIX, script),
+            ControllerKind::RandomWalk(seed) => write!(f, "random:{}", seed),
+            ControllerKind::Greedy => write!(f, "greedy"),
+            ControllerKind::WallFollower { right_hand: true } => write!(f, "wall:right"),
+            ControllerKind::WallFollower { right_hand: false } => write!(f, "wall:left"),
+            ControllerKind::External => write!(f, "external"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::{
+        element::{Element, RawAdventurer, RawMountain, RawTreasure},
+        grid::Grid,
+        map::Map,
+        observation::Sight,
+    };
+
+    /* MOCK INIT */
+
+    /// Observation of Lara at (1, 1) on a map of 4 x 3, with mountains at
+    /// (1, 0) and (2, 1) and a treasure at (3, 2).
+    fn init_mock_observation(orientation: Orientation) -> Observation {
+        let mut content = HashMap::new();
+        content.insert(
+            "M".to_string(),
+            vec![
+                Element::RawMountain(RawMountain::new(1, 0)),
+                Element::RawMountain(RawMountain::new(2, 1)),
+            ],
+        );
+        content.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(3, 2, 1))],
+        );
+        let mut the_map = Map::new(4, 3, content);
+        the_map.occupy((1, 1));
+        let adventurer = RawAdventurer::new("Lara".to_string(), 1, 1, orientation, String::new());
+
+        Observation::new(&the_map, &[adventurer], 0, Sight::default())
+    }
+
+    /* SHOULD PARSE CONTROLLER */
+
+    #[parameterized(raw_value = { "pattern", "random:7", "greedy", "wall:left", "external", "script:ahead=free?A;D" })]
+    fn should_read_back_controller_kind(raw_value: &str) {
+        let kind: ControllerKind = raw_value.parse().unwrap();
+
+        assert_eq!(kind.to_string(), raw_value);
+    }
+
+    #[parameterized(
+        raw_value = { "random", "wall", "random:x", "smart" },
+        expected = {
+            Ok(ControllerKind::RandomWalk(0)),
+            Ok(ControllerKind::WallFollower { right_hand: true }),
+            Err("Invalid seed : x".to_string()),
+            Err("Unknown controller : smart".to_string())
+        })]
+    fn should_parse_controller_kind(raw_value: &str, expected: Result<ControllerKind, String>) {
+        assert_eq!(raw_value.parse::<ControllerKind>(), expected);
+    }
+
+    /* SHOULD DECIDE */
+
+    #[test]
+    fn should_play_pattern_of_adventurer_from_its_cursor() {
+        // Given
+        let mut adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            1,
+            1,
+            Orientation::North,
+            "AGx".to_string(),
+        );
+        adventurer.skip_instruction();
+
+        // When
+        let instructions: Vec<Instruction> = (0..3)
+            .map(|_| {
+                let observation = Observation::blind(&adventurer, Grid::Square);
+                let instruction = PatternController.decide(&observation);
+                adventurer.skip_instruction();
+                instruction
+            })
+            .collect();
+
+        // Then
+        assert_eq!(
+            instructions,
+            vec![Instruction::TurnLeft, Instruction::Wait, Instruction::Wait]
+        );
+    }
+
+    #[parameterized(
+        orientation = { Orientation::South, Orientation::East, Orientation::North, Orientation::West },
+        expected = { Instruction::Advance, Instruction::TurnRight, Instruction::TurnRight, Instruction::TurnLeft })]
+    fn should_head_for_nearest_treasure_in_sight(orientation: Orientation, expected: Instruction) {
+        // Given: the way to the treasure starts south
+        let observation = init_mock_observation(orientation);
+
+        // When + Then
+        assert_eq!(GreedyController.decide(&observation), expected);
+    }
+
+    #[test]
+    fn should_follow_wall_on_right_hand() {
+        // Given: facing west, with a mountain north
+        let observation = init_mock_observation(Orientation::West);
+        let mut controller = WallFollower::new(true);
+
+        // When
+        let first = controller.decide(&observation);
+
+        // Then
+        assert_eq!(first, Instruction::Advance);
+    }
+
+    #[test]
+    fn should_turn_towards_free_wall_side_then_advance() {
+        // Given: facing north at a mountain, with a free cell west
+        let observation = init_mock_observation(Orientation::North);
+        let mut controller = WallFollower::new(false);
+
+        // When
+        let first = controller.decide(&observation);
+        let second = controller.decide(&init_mock_observation(Orientation::West));
+
+        // Then
+        assert_eq!(first, Instruction::TurnLeft);
+        assert_eq!(second, Instruction::Advance);
+    }
+
+    #[test]
+    fn should_walk_randomly_the_same_for_the_same_seed() {
+        // Given
+        let observation = init_mock_observation(Orientation::South);
+        let mut first = RandomWalk::new(3);
+        let mut second = RandomWalk::new(3);
+
+        // When + Then
+        for _ in 0..20 {
+            assert_eq!(first.decide(&observation), second.decide(&observation));
+        }
+    }
+
+    #[test]
+    fn should_only_turn_randomly_when_blocked() {
+        // Given
+        let observation = init_mock_observation(Orientation::North);
+        let mut controller = RandomWalk::new(0);
+
+        // When + Then
+        for _ in 0..20 {
+            assert_ne!(controller.decide(&observation), Instruction::Advance);
+        }
+    }
+
+    #[test]
+    fn should_wait_until_commanded_then_play_command() {
+        // Given
+        let mut observation = init_mock_observation(Orientation::North);
+
+        // When
+        let before = ExternalController.decide(&observation);
+        observation.command = Some(Instruction::TurnLeft);
+
+        // Then
+        assert_eq!(before, Instruction::Wait);
+        assert_eq!(
+            ExternalController.decide(&observation),
+            Instruction::TurnLeft
+        );
+    }
+}
@@ -1,9 +1,18 @@
+use std::{fmt, str::FromStr};
+
+use crate::{controller::ControllerKind, grid::Grid, map::Topology};
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Element {
     RawMap(RawMap),
     RawMountain(RawMountain),
     RawTreasure(RawTreasure),
     RawAdventurer(RawAdventurer),
+    RawTerrain(RawTerrain),
+    RawPortal(RawPortal),
+    RawBank(RawBank),
+    RawTopology(Topology),
+    RawGrid(Grid),
     Empty(Empty),
 }
 
@@ -15,25 +24,56 @@ impl Element {
             Element::RawMountain(mountain) => Some(mountain.position),
             Element::RawTreasure(treasure) => Some(treasure.position),
             Element::RawAdventurer(adventurer) => Some(adventurer.position),
+            Element::RawTerrain(terrain) => Some(terrain.position),
+            Element::RawPortal(portal) => Some(portal.position),
+            Element::RawBank(bank) => Some(bank.position),
+            Element::RawTopology(_) | Element::RawGrid(_) => None,
         }
     }
 }
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum Orientation {
     North,
     East,
     South,
     West,
+    NorthEast,
+    SouthEast,
+    SouthWest,
+    NorthWest,
 }
 
 impl Orientation {
     pub fn from_char(raw_value: char) -> Orientation {
+        Self::try_from_char(raw_value)
+            .unwrap_or_else(|| panic!("Unknown orientation : {}", raw_value))
+    }
+
+    pub fn try_from_char(raw_value: char) -> Option<Orientation> {
+        match raw_value {
+            'N' => Some(Orientation::North),
+            'S' => Some(Orientation::South),
+            'E' => Some(Orientation::East),
+            'O' => Some(Orientation::West),
+            _ => None,
+        }
+    }
+
+    /// Parses the orientation field of scenario files, including the
+    /// diagonal orientations of hex grids.
+    pub fn try_from_str(raw_value: &str) -> Option<Orientation> {
         match raw_value {
-            'N' => Orientation::North,
-            'S' => Orientation::South,
-            'E' => Orientation::East,
-            'O' => Orientation::West,
-            _ => panic!("Unknown orientation : {}", raw_value),
+            "NE" => Some(Orientation::NorthEast),
+            "SE" => Some(Orientation::SouthEast),
+            "SO" => Some(Orientation::SouthWest),
+            "NO" => Some(Orientation::NorthWest),
+            _ => {
+                let mut chars = raw_value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(raw_value), None) => Self::try_from_char(raw_value),
+                    _ => None,
+                }
+            }
         }
     }
 
@@ -43,6 +83,54 @@ impl Orientation {
             Orientation::South => "S".to_string(),
             Orientation::West => "O".to_string(),
             Orientation::East => "E".to_string(),
+            Orientation::NorthEast => "NE".to_string(),
+            Orientation::SouthEast => "SE".to_string(),
+            Orientation::SouthWest => "SO".to_string(),
+            Orientation::NorthWest => "NO".to_string(),
+        }
+    }
+}
+
+/// Ground of a cell, kept in its own layer under mountains, treasures and
+/// adventurers.
+///
+/// Water cannot be entered, entering a swamp costs an extra turn, an
+/// adventurer entering ice slides on until something stops it, and a
+/// conveyor carries it one cell along its orientation and cannot be entered
+/// against it.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Terrain {
+    Plain,
+    Water,
+    Swamp,
+    Ice,
+    Conveyor(Orientation),
+}
+
+impl Terrain {
+    /// Record letter of the terrain in scenario files.
+    pub fn key(&self) -> Option<&'static str> {
+        match self {
+            Terrain::Plain => None,
+            Terrain::Water => Some("W"),
+            Terrain::Swamp => Some("S"),
+            Terrain::Ice => Some("I"),
+            Terrain::Conveyor(_) => Some("V"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct RawTerrain {
+    pub position: (usize, usize),
+    pub terrain: Terrain,
+}
+
+impl RawTerrain {
+    pub fn new(x: usize, y: usize, terrain: Terrain) -> Self {
+        Self {
+            position: (x, y),
+            terrain,
         }
     }
 }
@@ -95,13 +183,118 @@ impl RawTreasure {
     }
 }
 
+/// Number of instructions an adventurer executes over a number of turns:
+/// `2` executes two instructions every turn, `1/2` one every other turn.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Speed {
+    pub actions: usize,
+    pub turns: usize,
+}
+
+impl Speed {
+    pub fn new(actions: usize, turns: usize) -> Self {
+        Self { actions, turns }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Speed::default()
+    }
+
+    /// Instructions to execute on `turn` (counted from 0), spread so that
+    /// the adventurer acts on the first turn and as evenly as possible after.
+    pub fn actions_on_turn(&self, turn: usize) -> usize {
+        let done_before = |turn: usize| (turn * self.actions).div_ceil(self.turns);
+
+        done_before(turn + 1) - done_before(turn)
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::new(1, 1)
+    }
+}
+
+impl FromStr for Speed {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        let (actions, turns) = raw_value.split_once('/').unwrap_or((raw_value, "1"));
+
+        match (actions.parse(), turns.parse()) {
+            (Ok(actions), Ok(turns)) if actions > 0 && turns > 0 => Ok(Speed::new(actions, turns)),
+            _ => Err(format!("Invalid speed : {}", raw_value)),
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.turns == 1 {
+            write!(f, "{}", self.actions)
+        } else {
+            write!(f, "{}/{}", self.actions, self.turns)
+        }
+    }
+}
+
+/// Cell where adventurers drop the treasure they carry to score it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RawBank {
+    pub position: (usize, usize),
+}
+
+impl RawBank {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { position: (x, y) }
+    }
+}
+
+/// One end of a pair of linked portals: an adventurer entering `position`
+/// comes out at `destination`, keeping its orientation.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RawPortal {
+    pub position: (usize, usize),
+    pub destination: (usize, usize),
+}
+
+impl RawPortal {
+    pub fn new(x1: usize, y1: usize, x2: usize, y2: usize) -> Self {
+        Self {
+            position: (x1, y1),
+            destination: (x2, y2),
+        }
+    }
+
+    /// The other end of the pair.
+    pub fn reversed(&self) -> RawPortal {
+        RawPortal {
+            position: self.destination,
+            destination: self.position,
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct RawAdventurer {
     pub name: String,
     pub position: (usize, usize),
     pub orientation: Orientation,
     pub pattern: String,
+    /// Byte offset in `pattern` of the next instruction to play, so that
+    /// playing an instruction never copies the pattern.
+    pub cursor: usize,
+    /// Treasure carried, at most `capacity` when there is one.
     pub treasure: usize,
+    pub capacity: Option<usize>,
+    /// Treasure dropped on bank cells.
+    pub banked: usize,
+    pub speed: Speed,
+    /// Turns left to wait before executing the next instruction.
+    pub waiting: usize,
+    /// What chooses each instruction. Only adventurers driven by their
+    /// pattern have one.
+    pub controller: ControllerKind,
 }
 
 impl RawAdventurer {
@@ -117,10 +310,20 @@ impl RawAdventurer {
             position: (x, y),
             orientation,
             pattern,
+            cursor: 0,
             treasure: 0,
+            capacity: None,
+            banked: 0,
+            speed: Speed::default(),
+            waiting: 0,
+            controller: ControllerKind::Pattern,
         }
     }
 
+    pub fn with_speed(self, speed: Speed) -> Self {
+        Self { speed, ..self }
+    }
+
     pub fn update_position(&self, dx: isize, dy: isize) -> RawAdventurer {
         let (x, y) = &self.position;
         let new_x = (*x as isize + dx) as usize;
@@ -132,13 +335,64 @@ impl RawAdventurer {
         }
     }
 
-    pub fn update_treasure(&self) -> RawAdventurer {
-        RawAdventurer {
-            treasure: self.treasure + 1,
-            ..self.clone()
+    pub fn with_capacity(self, capacity: Option<usize>) -> Self {
+        Self { capacity, ..self }
+    }
+
+    pub fn with_controller(self, controller: ControllerKind) -> Self {
+        Self { controller, ..self }
+    }
+
+    /// Instructions left to play.
+    pub fn remaining_pattern(&self) -> &str {
+        &self.pattern[self.cursor..]
+    }
+
+    pub fn next_instruction(&self) -> Option<char> {
+        self.remaining_pattern().chars().next()
+    }
+
+    /// Moves the cursor past the next instruction, if any.
+    pub fn skip_instruction(&mut self) {
+        if let Some(instruction) = self.next_instruction() {
+            self.cursor += instruction.len_utf8();
         }
     }
 
+    /// Whether the whole pattern has been played. Adventurers driven by
+    /// another controller never finish.
+    pub fn is_finished(&self) -> bool {
+        self.controller == ControllerKind::Pattern && self.cursor >= self.pattern.len()
+    }
+
+    /// Drives the adventurer by `pattern`, starting over from its first
+    /// instruction.
+    pub fn set_pattern(&mut self, pattern: String) {
+        self.pattern = pattern;
+        self.cursor = 0;
+        self.controller = ControllerKind::Pattern;
+    }
+
+    pub fn update_treasure(&mut self) {
+        self.treasure += 1;
+    }
+
+    /// Treasure collected so far, whether still carried or banked.
+    pub fn collected(&self) -> usize {
+        self.treasure + self.banked
+    }
+
+    pub fn can_carry(&self) -> bool {
+        self.capacity
+            .is_none_or(|capacity| self.treasure < capacity)
+    }
+
+    /// Drops the carried treasure into the bank.
+    pub fn bank(&mut self) {
+        self.banked += self.treasure;
+        self.treasure = 0;
+    }
+
     pub fn get_orientation_to_string(&self) -> String {
         Orientation::from(self.orientation)
     }
@@ -159,14 +413,101 @@ mod tests {
         assert_eq!(Orientation::from_char(char), orientation);
     }
 
+    #[parameterized(
+        raw_value = { "N", "O", "NE", "SE", "SO", "NO", "X", "NN", "" },
+        expected = {
+            Some(Orientation::North),
+            Some(Orientation::West),
+            Some(Orientation::NorthEast),
+            Some(Orientation::SouthEast),
+            Some(Orientation::SouthWest),
+            Some(Orientation::NorthWest),
+            None,
+            None,
+            None,
+        })]
+    fn should_get_orientation_when_given_field(raw_value: &str, expected: Option<Orientation>) {
+        assert_eq!(Orientation::try_from_str(raw_value), expected);
+    }
+
+    #[test]
+    fn should_get_no_orientation_when_given_unknown_character() {
+        assert_eq!(Orientation::try_from_char('X'), None);
+    }
+
     #[test]
     #[should_panic(expected = "Unknown orientation : X")]
     fn should_panic_when_given_unknown_character() {
         let _ = Orientation::from_char('X');
     }
 
+    /* SHOULD TEST SPEED */
+
+    #[parameterized(
+        raw_value = { "1", "2", "1/2", "3/2" },
+        expected = { Speed::new(1, 1), Speed::new(2, 1), Speed::new(1, 2), Speed::new(3, 2) })]
+    fn should_parse_speed(raw_value: &str, expected: Speed) {
+        assert_eq!(raw_value.parse(), Ok(expected));
+        assert_eq!(expected.to_string(), raw_value);
+    }
+
+    #[parameterized(raw_value = { "0", "1/0", "x", "1/x", "" })]
+    fn should_reject_invalid_speed(raw_value: &str) {
+        assert!(raw_value.parse::<Speed>().is_err());
+    }
+
+    #[parameterized(
+        speed = { Speed::new(1, 1), Speed::new(2, 1), Speed::new(1, 2), Speed::new(3, 2) },
+        expected = { vec![1, 1, 1, 1], vec![2, 2, 2, 2], vec![1, 0, 1, 0], vec![2, 1, 2, 1] })]
+    fn should_spread_actions_over_turns(speed: Speed, expected: Vec<usize>) {
+        let actions: Vec<usize> = (0..4).map(|turn| speed.actions_on_turn(turn)).collect();
+
+        assert_eq!(actions, expected);
+    }
+
     /* SHOULD TEST RAWADVENTURER */
 
+    #[parameterized(
+        capacity = { None, Some(2), Some(3) },
+        expected = { true, false, true })]
+    fn should_carry_up_to_capacity(capacity: Option<usize>, expected: bool) {
+        // Given
+        let mut adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::North,
+            "A".to_string(),
+        )
+        .with_capacity(capacity);
+        adventurer.update_treasure();
+        adventurer.update_treasure();
+
+        // When + Then
+        assert_eq!(adventurer.can_carry(), expected);
+    }
+
+    #[test]
+    fn should_move_carried_treasure_to_bank() {
+        // Given
+        let mut adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::North,
+            "A".to_string(),
+        );
+        adventurer.update_treasure();
+        adventurer.bank();
+        adventurer.update_treasure();
+
+        // When
+        adventurer.bank();
+
+        // Then
+        assert_eq!((adventurer.treasure, adventurer.banked), (0, 2));
+    }
+
     #[test]
     fn should_init_adventurer() {
         // Given
@@ -214,10 +555,38 @@ mod tests {
         assert_eq!(new_adventurer, expected_adventurer);
     }
 
+    #[test]
+    fn should_walk_pattern_with_cursor() {
+        // Given
+        let mut adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::North,
+            "AD".to_string(),
+        );
+
+        // When
+        adventurer.skip_instruction();
+
+        // Then
+        assert_eq!(adventurer.next_instruction(), Some('D'));
+        assert_eq!(adventurer.remaining_pattern(), "D");
+        assert_eq!(adventurer.pattern, "AD");
+
+        adventurer.skip_instruction();
+        adventurer.skip_instruction();
+        assert!(adventurer.is_finished());
+        assert_eq!(adventurer.next_instruction(), None);
+
+        adventurer.set_pattern("G".to_string());
+        assert_eq!(adventurer.next_instruction(), Some('G'));
+    }
+
     #[test]
     fn should_add_treasure_to_adventurer() {
         // Given
-        let adventurer = RawAdventurer::new(
+        let mut new_adventurer = RawAdventurer::new(
             "Lara".to_string(),
             0,
             0,
@@ -226,7 +595,7 @@ mod tests {
         );
 
         // When
-        let new_adventurer = adventurer.update_treasure();
+        new_adventurer.update_treasure();
 
         // Then
         assert_eq!(new_adventurer.position, (0, 0));
@@ -0,0 +1,352 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    controller::{ControllerKind, Instruction},
+    element::Element,
+    generator::{self, GeneratorError, GeneratorOptions},
+    observation::{Observation, Sight},
+    simulation::{ConflictPolicy, ResolutionMode, Simulation},
+};
+
+/// Scenario each episode of an environment starts from.
+#[derive(Debug, Clone)]
+pub enum ScenarioSource {
+    /// The same scenario for every episode, shared by the clones of the
+    /// environment.
+    Fixed(Arc<HashMap<String, Vec<Element>>>),
+    /// A scenario generated from the seed of the episode, replacing the seed
+    /// of the options.
+    Generated(GeneratorOptions),
+}
+
+/// Episodes of the game played one turn at a time by code choosing the
+/// instruction of every adventurer, such as agents being trained.
+///
+/// Each adventurer gets its observation and is rewarded with the treasure it
+/// collected on the turn, whether or not the map has banks. An episode is
+/// done once no treasure is left to collect or after `max_turns` turns. The
+/// same seed always gives the same episode for the same instructions, and
+/// cloning an environment copies the episode so far, to try several
+/// instructions from the same turn.
+#[derive(Clone)]
+pub struct Environment {
+    source: ScenarioSource,
+    pub max_turns: usize,
+    pub sight: Sight,
+    /// Resolution of the moves. A random conflict policy is seeded by the
+    /// seed of the episode.
+    pub resolution: ResolutionMode,
+    pub simulation: Simulation,
+}
+
+impl Environment {
+    /// Environment in the episode of seed 0. Fails when generated scenarios
+    /// cannot hold their treasures and adventurers.
+    pub fn new(source: ScenarioSource) -> Result<Environment, GeneratorError> {
+        let sight = Sight::default();
+        let resolution = ResolutionMode::Sequential;
+        let simulation = Self::episode(&source, sight, resolution, 0)?;
+
+        Ok(Environment {
+            source,
+            max_turns: 200,
+            sight,
+            resolution,
+            simulation,
+        })
+    }
+
+    pub fn with_max_turns(self, max_turns: usize) -> Environment {
+        Environment { max_turns, ..self }
+    }
+
+    pub fn with_sight(self, sight: Sight) -> Environment {
+        Environment {
+            simulation: self.simulation.with_sight(sight),
+            sight,
+            ..self
+        }
+    }
+
+    pub fn with_resolution(self, resolution: ResolutionMode) -> Environment {
+        Environment {
+            simulation: self.simulation.with_resolution(Self::seeded(resolution, 0)),
+            resolution,
+            ..self
+        }
+    }
+
+    fn episode(
+        source: &ScenarioSource,
+        sight: Sight,
+        resolution: ResolutionMode,
+        seed: u64,
+    ) -> Result<Simulation, GeneratorError> {
+        let mut content = match source {
+            ScenarioSource::Fixed(content) => HashMap::clone(content),
+            ScenarioSource::Generated(options) => {
+                generator::generate(&GeneratorOptions { seed, ..*options })?
+            }
+        };
+        for element in content.values_mut().flatten() {
+            if let Element::RawAdventurer(adventurer) = element {
+                adventurer.controller = ControllerKind::External;
+            }
+        }
+
+        Ok(Simulation::from_hashmap(&content)
+            .with_resolution(Self::seeded(resolution, seed))
+            .with_sight(sight))
+    }
+
+    fn seeded(resolution: ResolutionMode, seed: u64) -> ResolutionMode {
+        match resolution {
+            ResolutionMode::Simultaneous(ConflictPolicy::Random(_)) => {
+                ResolutionMode::Simultaneous(ConflictPolicy::Random(seed))
+            }
+            other => other,
+        }
+    }
+
+    /// Starts the episode of `seed` over and returns the observation of each
+    /// adventurer, in declaration order.
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        // Whether a scenario can be generated does not depend on its seed
+        self.simulation = Self::episode(&self.source, self.sight, self.resolution, seed)
+            .expect("The options generated the first episode");
+
+        self.observe()
+    }
+
+    /// Plays one turn where each adventurer executes its instruction in
+    /// `actions`, as many times as its speed allows. Returns the observation
+    /// and the reward of each adventurer, and whether the episode is done.
+    pub fn step(&mut self, actions: &[Instruction]) -> (Vec<Observation>, Vec<usize>, bool) {
+        assert_eq!(
+            actions.len(),
+            self.simulation.adventurers.len(),
+            "Expected one action per adventurer"
+        );
+
+        let before: Vec<usize> = self.collected();
+        for (index, action) in actions.iter().enumerate() {
+            self.simulation.command(index, *action);
+        }
+        self.simulation.step();
+        let rewards = self
+            .collected()
+            .iter()
+            .zip(before)
+            .map(|(after, before)| after - before)
+            .collect();
+
+        (self.observe(), rewards, self.is_done())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.simulation.turn >= self.max_turns || self.simulation.map.remaining_treasure() == 0
+    }
+
+    pub fn observe(&self) -> Vec<Observation> {
+        (0..self.simulation.adventurers.len())
+            .map(|index| {
+                Observation::new(
+                    &self.simulation.map,
+                    &self.simulation.adventurers,
+                    index,
+                    self.sight,
+                )
+            })
+            .collect()
+    }
+
+    fn collected(&self) -> Vec<usize> {
+        self.simulation
+            .adventurers
+            .iter()
+            .map(|adventurer| adventurer.collected())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{element::Orientation, file::read_scenario};
+
+    /* MOCK INIT */
+
+    // L 1 . 1 . B
+    // . . . T . .
+    //
+    // Lara faces the first treasure and Toto the second one.
+    fn init_mock_environment() -> Environment {
+        let content = read_scenario(
+            "C - 6 - 2\nT - 1 - 0 - 1\nT - 3 - 0 - 1\nB - 5 - 0\n\
+             A - Lara - 0 - 0 - E - GGG\nA - Toto - 3 - 1 - N - GGG\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        Environment::new(ScenarioSource::Fixed(Arc::new(content)))
+            .unwrap()
+            .with_max_turns(10)
+    }
+
+    fn positions(observations: &[Observation]) -> Vec<(usize, usize)> {
+        observations
+            .iter()
+            .map(|observation| observation.position)
+            .collect()
+    }
+
+    /* SHOULD RESET */
+
+    #[test]
+    fn should_observe_every_adventurer_on_reset() {
+        // Given
+        let mut environment = init_mock_environment();
+        environment.step(&[Instruction::Advance, Instruction::TurnLeft]);
+
+        // When
+        let observations = environment.reset(0);
+
+        // Then
+        assert_eq!(positions(&observations), vec![(0, 0), (3, 1)]);
+        assert_eq!(observations[1].orientation, Orientation::North);
+        assert_eq!(environment.simulation.turn, 0);
+        assert_eq!(
+            observations[0]
+                .towards(Orientation::East)
+                .unwrap()
+                .treasure(),
+            1
+        );
+    }
+
+    #[test]
+    fn should_generate_same_episode_for_same_seed() {
+        // Given
+        let mut environment =
+            Environment::new(ScenarioSource::Generated(GeneratorOptions::default())).unwrap();
+
+        // When
+        let first = environment.reset(3);
+        let second = environment.reset(3);
+        let other = environment.reset(4);
+
+        // Then
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn should_fail_when_scenario_cannot_be_generated() {
+        let options = GeneratorOptions {
+            width: 1,
+            height: 1,
+            ..GeneratorOptions::default()
+        };
+
+        assert!(Environment::new(ScenarioSource::Generated(options)).is_err());
+    }
+
+    /* SHOULD STEP */
+
+    #[test]
+    fn should_follow_actions_and_reward_collected_treasure() {
+        // Given
+        let mut environment = init_mock_environment();
+
+        // When
+        let (observations, rewards, done) =
+            environment.step(&[Instruction::Advance, Instruction::Wait]);
+
+        // Then
+        assert_eq!(positions(&observations), vec![(1, 0), (3, 1)]);
+        assert_eq!(rewards, vec![1, 0]);
+        assert!(!done);
+    }
+
+    #[test]
+    fn should_not_reward_banking_treasure() {
+        // Given
+        let mut environment = init_mock_environment();
+
+        // When: Toto collects the second treasure, then banks it
+        let rewards: Vec<usize> = [
+            Instruction::Advance,
+            Instruction::TurnRight,
+            Instruction::Advance,
+            Instruction::Advance,
+        ]
+        .iter()
+        .map(|action| environment.step(&[Instruction::Wait, *action]).1[1])
+        .collect();
+
+        // Then
+        assert_eq!(rewards, vec![1, 0, 0, 0]);
+        assert_eq!(environment.simulation.adventurers[1].banked, 1);
+    }
+
+    #[test]
+    fn should_be_done_once_every_treasure_is_collected_or_at_turn_limit() {
+        // Given
+        let mut environment = init_mock_environment();
+        environment.step(&[Instruction::Advance, Instruction::Wait]);
+        environment.step(&[Instruction::Advance, Instruction::Wait]);
+
+        // When
+        let (_, rewards, done) = environment.step(&[Instruction::Advance, Instruction::Wait]);
+
+        // Then
+        assert_eq!(rewards, vec![1, 0]);
+        assert!(done);
+        environment.reset(0);
+        for _ in 0..9 {
+            assert!(!environment.step(&[Instruction::Wait, Instruction::Wait]).2);
+        }
+        assert!(environment.step(&[Instruction::Wait, Instruction::Wait]).2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected one action per adventurer")]
+    fn should_panic_when_actions_are_missing() {
+        init_mock_environment().step(&[Instruction::Advance]);
+    }
+
+    /* SHOULD CLONE */
+
+    #[test]
+    fn should_play_clone_apart_from_original() {
+        // Given
+        let mut environment = init_mock_environment();
+        environment.step(&[Instruction::Advance, Instruction::Wait]);
+
+        // When
+        let mut clone = environment.clone();
+        let from_clone = clone.step(&[Instruction::TurnLeft, Instruction::Advance]);
+        let from_original = environment.step(&[Instruction::TurnLeft, Instruction::Advance]);
+
+        // Then
+        assert_eq!(from_clone, from_original);
+        assert_eq!(clone.simulation.turn, 2);
+    }
+
+    #[test]
+    fn should_share_fixed_scenario_with_clone() {
+        // Given
+        let environment = init_mock_environment();
+
+        // When
+        let clone = environment.clone();
+
+        // Then
+        match (&environment.source, &clone.source) {
+            (ScenarioSource::Fixed(original), ScenarioSource::Fixed(cloned)) => {
+                assert!(Arc::ptr_eq(original, cloned))
+            }
+            _ => panic!("Expected fixed scenarios"),
+        }
+    }
+}
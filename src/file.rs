@@ -1,12 +1,29 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, prelude::*, BufReader, Error};
+use std::io::{self, prelude::*, BufReader, Error, ErrorKind};
+use std::str::FromStr;
 
-use crate::element::{self, Element, Orientation, RawAdventurer, RawMap, RawMountain, RawTreasure};
+use crate::{
+    controller::ControllerKind,
+    grid::Grid,
+    map::Topology,
+    script::{Script, SCRIPT_PREFIX},
+};
+
+use crate::element::{
+    self, Element, Orientation, RawAdventurer, RawBank, RawMap, RawMountain, RawPortal, RawTerrain,
+    RawTreasure, Speed, Terrain,
+};
 
 pub fn file_to_hashmap(file: File) -> Result<HashMap<String, Vec<Element>>, Error> {
+    read_scenario(BufReader::new(file))
+}
+
+/// Reads a scenario from any buffered reader (file, string, stdin...). A
+/// malformed record (missing field, number that does not parse, unknown
+/// orientation) is reported as an `InvalidData` error rather than a panic.
+pub fn read_scenario<R: BufRead>(reader: R) -> Result<HashMap<String, Vec<Element>>, Error> {
     let separator = "-";
-    let reader = BufReader::new(file);
 
     let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
 
@@ -16,16 +33,38 @@ pub fn file_to_hashmap(file: File) -> Result<HashMap<String, Vec<Element>>, Erro
 
         match content[0] {
             "C" => {
-                let value = RawMap::new(content[1].parse().unwrap(), content[2].parse().unwrap());
+                let value = RawMap::new(parse_field(&content, 1)?, parse_field(&content, 2)?);
                 hash_map = insert_into_map(
                     hash_map,
                     content[0].to_owned(),
                     element::Element::RawMap(value),
                 )
             }
+            "Grid" => {
+                let value: Grid = content
+                    .get(1)
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| invalid_line(&content))?;
+                hash_map = insert_into_map(
+                    hash_map,
+                    content[0].to_owned(),
+                    element::Element::RawGrid(value),
+                )
+            }
+            "Topology" => {
+                // Topology names contain the separator
+                let value: Topology = content[1..]
+                    .join(separator)
+                    .parse()
+                    .map_err(|_| invalid_line(&content))?;
+                hash_map = insert_into_map(
+                    hash_map,
+                    content[0].to_owned(),
+                    element::Element::RawTopology(value),
+                )
+            }
             "M" => {
-                let value =
-                    RawMountain::new(content[1].parse().unwrap(), content[2].parse().unwrap());
+                let value = RawMountain::new(parse_field(&content, 1)?, parse_field(&content, 2)?);
                 hash_map = insert_into_map(
                     hash_map,
                     content[0].to_owned(),
@@ -34,9 +73,9 @@ pub fn file_to_hashmap(file: File) -> Result<HashMap<String, Vec<Element>>, Erro
             }
             "T" => {
                 let value = RawTreasure::new(
-                    content[1].parse().unwrap(),
-                    content[2].parse().unwrap(),
-                    content[3].parse().unwrap(),
+                    parse_field(&content, 1)?,
+                    parse_field(&content, 2)?,
+                    parse_field(&content, 3)?,
                 );
                 hash_map = insert_into_map(
                     hash_map,
@@ -45,38 +84,231 @@ pub fn file_to_hashmap(file: File) -> Result<HashMap<String, Vec<Element>>, Erro
                 )
             }
             "A" => {
-                let orientation: Vec<char> = content[4].chars().collect();
+                let orientation = content
+                    .get(4)
+                    .and_then(|value| Orientation::try_from_str(value))
+                    .ok_or_else(|| invalid_line(&content))?;
+                let speed = match content.get(6) {
+                    Some(_) => parse_field(&content, 6)?,
+                    None => Speed::default(),
+                };
+                let capacity = match content.get(7) {
+                    Some(_) => Some(parse_field(&content, 7)?),
+                    None => None,
+                };
+                let mut pattern: String = parse_field(&content, 5)?;
+                let controller = match pattern.strip_prefix(SCRIPT_PREFIX) {
+                    Some(source) => {
+                        ControllerKind::Script(source.parse::<Script>().map_err(|error| {
+                            Error::new(
+                                ErrorKind::InvalidData,
+                                format!("Invalid script : {}", error),
+                            )
+                        })?)
+                    }
+                    None => ControllerKind::Pattern,
+                };
+                if controller != ControllerKind::Pattern {
+                    pattern.clear();
+                }
                 let value = RawAdventurer::new(
-                    content[1].to_string(),
-                    content[2].parse().unwrap(),
-                    content[3].parse().unwrap(),
-                    Orientation::from_char(orientation[0]),
-                    content[5].to_string(),
-                );
+                    parse_field(&content, 1)?,
+                    parse_field(&content, 2)?,
+                    parse_field(&content, 3)?,
+                    orientation,
+                    pattern,
+                )
+                .with_speed(speed)
+                .with_capacity(capacity)
+                .with_controller(controller);
                 hash_map = insert_into_map(
                     hash_map,
                     content[0].to_owned(),
                     element::Element::RawAdventurer(value),
                 )
             }
-            _ => println!("Ignore value : {}", content[0]),
+            "B" => {
+                let value = RawBank::new(parse_field(&content, 1)?, parse_field(&content, 2)?);
+                hash_map = insert_into_map(
+                    hash_map,
+                    content[0].to_owned(),
+                    element::Element::RawBank(value),
+                )
+            }
+            "P" => {
+                let value = RawPortal::new(
+                    parse_field(&content, 1)?,
+                    parse_field(&content, 2)?,
+                    parse_field(&content, 3)?,
+                    parse_field(&content, 4)?,
+                );
+                hash_map = insert_into_map(
+                    hash_map,
+                    content[0].to_owned(),
+                    element::Element::RawPortal(value),
+                )
+            }
+            "W" | "S" | "I" | "V" => {
+                let terrain = match content[0] {
+                    "W" => Terrain::Water,
+                    "S" => Terrain::Swamp,
+                    "I" => Terrain::Ice,
+                    _ => Terrain::Conveyor(
+                        content
+                            .get(3)
+                            .and_then(|value| Orientation::try_from_str(value))
+                            .ok_or_else(|| invalid_line(&content))?,
+                    ),
+                };
+                let value = RawTerrain::new(
+                    parse_field(&content, 1)?,
+                    parse_field(&content, 2)?,
+                    terrain,
+                );
+                hash_map = insert_into_map(
+                    hash_map,
+                    content[0].to_owned(),
+                    element::Element::RawTerrain(value),
+                )
+            }
+            _ => eprintln!("Ignore value : {}", content[0]),
         }
-        println!("{}", line);
     }
 
     Ok(hash_map)
 }
 
+fn parse_field<T: FromStr>(content: &[&str], index: usize) -> io::Result<T> {
+    content
+        .get(index)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_line(content))
+}
+
+fn invalid_line(content: &[&str]) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid line : {}", content.join("-")),
+    )
+}
+
 pub fn generate_map_file(
     hashmap: &HashMap<String, Vec<Element>>,
     filename: &str,
 ) -> io::Result<()> {
-    let mut file = File::create(filename)?;
+    write_result(hashmap, &mut File::create(filename)?)
+}
+
+pub fn generate_scenario_file(
+    hashmap: &HashMap<String, Vec<Element>>,
+    filename: &str,
+) -> io::Result<()> {
+    write_scenario(hashmap, &mut File::create(filename)?)
+}
+
+/// Writes the result of a run: the treasure of each adventurer instead of
+/// its pattern. When the map has banks, the treasure is written as
+/// `carried:banked`.
+pub fn write_result<W: Write>(
+    hashmap: &HashMap<String, Vec<Element>>,
+    writer: &mut W,
+) -> io::Result<()> {
+    write_map_elements(hashmap, writer)?;
+    let has_banks = hashmap.get("B").is_some_and(|banks| !banks.is_empty());
+
+    if let Some(raw_adventurers) = hashmap.get("A") {
+        for element in raw_adventurers {
+            if let Element::RawAdventurer(adventurer) = element {
+                write!(
+                    writer,
+                    "A - {} - {} - {} - {} - {}",
+                    adventurer.name,
+                    adventurer.position.0,
+                    adventurer.position.1,
+                    adventurer.get_orientation_to_string(),
+                    adventurer.treasure
+                )?;
+                if has_banks {
+                    write!(writer, ":{}", adventurer.banked)?;
+                }
+                write_options(adventurer, writer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a scenario in the input format read by `read_scenario`, with the
+/// pattern or the script of each adventurer instead of its treasure.
+pub fn write_scenario<W: Write>(
+    hashmap: &HashMap<String, Vec<Element>>,
+    writer: &mut W,
+) -> io::Result<()> {
+    write_map_elements(hashmap, writer)?;
+
+    if let Some(raw_adventurers) = hashmap.get("A") {
+        for element in raw_adventurers {
+            if let Element::RawAdventurer(adventurer) = element {
+                let behaviour = match &adventurer.controller {
+                    ControllerKind::Script(script) => format!("{}{}", SCRIPT_PREFIX, script),
+                    _ => adventurer.remaining_pattern().to_string(),
+                };
+                write!(
+                    writer,
+                    "A - {} - {} - {} - {} - {}",
+                    adventurer.name,
+                    adventurer.position.0,
+                    adventurer.position.1,
+                    adventurer.get_orientation_to_string(),
+                    behaviour
+                )?;
+                write_options(adventurer, writer)?;
+            }
+        }
+    }
+
+    Ok(())
+}
 
+/// Ends an adventurer line with its optional speed and capacity, leaving out
+/// what is not needed so that files without them stay unchanged.
+fn write_options<W: Write>(adventurer: &RawAdventurer, writer: &mut W) -> io::Result<()> {
+    match adventurer.capacity {
+        Some(capacity) => writeln!(writer, " - {} - {}", adventurer.speed, capacity),
+        None if !adventurer.speed.is_default() => writeln!(writer, " - {}", adventurer.speed),
+        None => writeln!(writer),
+    }
+}
+
+fn write_map_elements<W: Write>(
+    hashmap: &HashMap<String, Vec<Element>>,
+    writer: &mut W,
+) -> io::Result<()> {
     if let Some(raw_maps) = hashmap.get("C") {
         for element in raw_maps {
             if let Element::RawMap(raw_map) = element {
-                writeln!(file, "C - {} - {}", raw_map.position.0, raw_map.position.1)?;
+                writeln!(
+                    writer,
+                    "C - {} - {}",
+                    raw_map.position.0, raw_map.position.1
+                )?;
+            }
+        }
+    }
+
+    if let Some(raw_grids) = hashmap.get("Grid") {
+        for element in raw_grids {
+            if let Element::RawGrid(grid) = element {
+                writeln!(writer, "Grid - {}", grid.name())?;
+            }
+        }
+    }
+
+    if let Some(raw_topologies) = hashmap.get("Topology") {
+        for element in raw_topologies {
+            if let Element::RawTopology(topology) = element {
+                writeln!(writer, "Topology - {}", topology.name())?;
             }
         }
     }
@@ -85,7 +317,7 @@ pub fn generate_map_file(
         for element in raw_mountains {
             if let Element::RawMountain(mountain) = element {
                 writeln!(
-                    file,
+                    writer,
                     "M - {} - {}",
                     mountain.position.0, mountain.position.1
                 )?;
@@ -97,7 +329,7 @@ pub fn generate_map_file(
         for element in raw_treasures {
             if let Element::RawTreasure(treasure) = element {
                 writeln!(
-                    file,
+                    writer,
                     "T - {} - {} - {}",
                     treasure.position.0, treasure.position.1, treasure.nb_treasure
                 )?;
@@ -105,22 +337,45 @@ pub fn generate_map_file(
         }
     }
 
-    if let Some(raw_adventurers) = hashmap.get("A") {
-        for element in raw_adventurers {
-            if let Element::RawAdventurer(adventurer) = element {
+    if let Some(raw_banks) = hashmap.get("B") {
+        for element in raw_banks {
+            if let Element::RawBank(bank) = element {
+                writeln!(writer, "B - {} - {}", bank.position.0, bank.position.1)?;
+            }
+        }
+    }
+
+    if let Some(raw_portals) = hashmap.get("P") {
+        for element in raw_portals {
+            if let Element::RawPortal(portal) = element {
                 writeln!(
-                    file,
-                    "A - {} - {} - {} - {} - {}",
-                    adventurer.name,
-                    adventurer.position.0,
-                    adventurer.position.1,
-                    adventurer.get_orientation_to_string(),
-                    adventurer.treasure
+                    writer,
+                    "P - {} - {} - {} - {}",
+                    portal.position.0,
+                    portal.position.1,
+                    portal.destination.0,
+                    portal.destination.1
                 )?;
             }
         }
     }
 
+    for key in ["W", "S", "I", "V"] {
+        for element in hashmap.get(key).into_iter().flatten() {
+            if let Element::RawTerrain(raw_terrain) = element {
+                write!(
+                    writer,
+                    "{} - {} - {}",
+                    key, raw_terrain.position.0, raw_terrain.position.1
+                )?;
+                if let Terrain::Conveyor(orientation) = raw_terrain.terrain {
+                    write!(writer, " - {}", Orientation::from(orientation))?;
+                }
+                writeln!(writer)?;
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -145,8 +400,11 @@ fn insert_into_map(
 
 #[cfg(test)]
 mod tests {
+    use parameterized::parameterized;
     use tempfile::NamedTempFile;
 
+    use crate::{generator::generate, generator::tests::random_options, rng::Rng};
+
     use super::*;
 
     #[test]
@@ -195,18 +453,70 @@ mod tests {
     }
 
     #[test]
-    fn test_generate_map_file() -> io::Result<()> {
+    fn should_read_back_generated_scenario_file() -> io::Result<()> {
         // Given
         let mut hashmap: HashMap<String, Vec<Element>> = HashMap::new();
+        hashmap.insert("C".to_string(), vec![Element::RawMap(RawMap::new(4, 4))]);
         hashmap.insert(
-            "C".to_string(),
-            vec![element::Element::RawMap(RawMap::new(4, 4))],
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(0, 1))],
+        );
+        hashmap.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(1, 2, 2))],
+        );
+        hashmap.insert(
+            "A".to_string(),
+            vec![Element::RawAdventurer(RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::South,
+                "GAADADAAGADA".to_string(),
+            ))],
+        );
+        let temp_file = NamedTempFile::new()?;
+
+        // When
+        generate_scenario_file(&hashmap, temp_file.path().to_str().unwrap())?;
+
+        // Then
+        let result = file_to_hashmap(temp_file.reopen()?)?;
+        assert_eq!(result, hashmap);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_scenario_from_string() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nT - 0 - 1 - 2\n";
+
+        // When
+        let result = read_scenario(content.as_bytes())?;
+
+        // Then
+        let mut expected: HashMap<String, Vec<Element>> = HashMap::new();
+        expected.insert("C".to_string(), vec![Element::RawMap(RawMap::new(3, 4))]);
+        expected.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 1, 2))],
         );
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_write_result_into_buffer() -> io::Result<()> {
+        // Given
+        let mut hashmap: HashMap<String, Vec<Element>> = HashMap::new();
+        hashmap.insert("C".to_string(), vec![Element::RawMap(RawMap::new(4, 4))]);
         hashmap.insert(
             "M".to_string(),
             vec![
-                Element::RawMountain(RawMountain { position: (0, 1) }),
-                Element::RawMountain(RawMountain { position: (1, 1) }),
+                Element::RawMountain(RawMountain::new(0, 1)),
+                Element::RawMountain(RawMountain::new(1, 1)),
             ],
         );
         hashmap.insert(
@@ -223,17 +533,303 @@ mod tests {
                 "GAADADAAGADA".to_string(),
             ))],
         );
+        let mut buffer: Vec<u8> = Vec::new();
 
         // When
-        generate_map_file(&hashmap, "test_map.txt")?;
+        write_result(&hashmap, &mut buffer)?;
 
         // Then
         let expected_content =
             "C - 4 - 4\nM - 0 - 1\nM - 1 - 1\nT - 1 - 2 - 2\nA - Lara - 0 - 0 - S - 0\n";
-        let actual_content = std::fs::read_to_string("test_map.txt")?;
-        assert_eq!(actual_content, expected_content);
+        assert_eq!(String::from_utf8(buffer).unwrap(), expected_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_speed_of_generated_map_file() -> io::Result<()> {
+        // Given
+        let mut hashmap: HashMap<String, Vec<Element>> = HashMap::new();
+        hashmap.insert("C".to_string(), vec![Element::RawMap(RawMap::new(4, 4))]);
+        hashmap.insert(
+            "A".to_string(),
+            vec![
+                Element::RawAdventurer(
+                    RawAdventurer::new(
+                        "Lara".to_string(),
+                        0,
+                        0,
+                        Orientation::South,
+                        "A".to_string(),
+                    )
+                    .with_speed(Speed::new(1, 2)),
+                ),
+                Element::RawAdventurer(RawAdventurer::new(
+                    "Toto".to_string(),
+                    1,
+                    0,
+                    Orientation::South,
+                    "A".to_string(),
+                )),
+            ],
+        );
+        let temp_file = NamedTempFile::new()?;
+
+        // When
+        generate_map_file(&hashmap, temp_file.path().to_str().unwrap())?;
+
+        // Then
+        let mut content = String::new();
+        temp_file.reopen()?.read_to_string(&mut content)?;
+        assert_eq!(
+            content,
+            "C - 4 - 4\nA - Lara - 0 - 0 - S - 0 - 1/2\nA - Toto - 1 - 0 - S - 0\n"
+        );
+        let speeds: Vec<Speed> = file_to_hashmap(temp_file.reopen()?)?["A"]
+            .iter()
+            .map(|element| match element {
+                Element::RawAdventurer(adventurer) => adventurer.speed,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(speeds, vec![Speed::new(1, 2), Speed::default()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_topology_header() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nTopology - cylinder-horizontal\n";
+
+        // When
+        let hashmap = read_scenario(content.as_bytes())?;
+        let mut buffer: Vec<u8> = Vec::new();
+        write_scenario(&hashmap, &mut buffer)?;
+
+        // Then
+        assert_eq!(
+            hashmap["Topology"],
+            vec![Element::RawTopology(Topology::CylinderHorizontal)]
+        );
+        assert_eq!(String::from_utf8(buffer).unwrap(), content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_hex_scenario() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nGrid - hex\nV - 0 - 1 - SE\nA - Lara - 1 - 1 - NO - AGDA\n";
+
+        // When
+        let hashmap = read_scenario(content.as_bytes())?;
+        let mut buffer: Vec<u8> = Vec::new();
+        write_scenario(&hashmap, &mut buffer)?;
+
+        // Then
+        assert_eq!(hashmap["Grid"], vec![Element::RawGrid(Grid::Hex)]);
+        assert_eq!(String::from_utf8(buffer).unwrap(), content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_scripted_adventurer() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nA - Lara - 1 - 1 - N - script:ahead!=free?D;A - 2\n";
+
+        // When
+        let hashmap = read_scenario(content.as_bytes())?;
+        let mut buffer: Vec<u8> = Vec::new();
+        write_scenario(&hashmap, &mut buffer)?;
+
+        // Then
+        let Element::RawAdventurer(adventurer) = &hashmap["A"][0] else {
+            panic!("Expected an adventurer");
+        };
+        assert_eq!(adventurer.pattern, "");
+        assert_eq!(
+            adventurer.controller,
+            ControllerKind::Script("ahead!=free?D;A".parse().unwrap())
+        );
+        assert_eq!(String::from_utf8(buffer).unwrap(), content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_terrain_records() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nW - 0 - 0\nS - 1 - 0\nI - 2 - 0\nV - 0 - 1 - E\n";
+
+        // When
+        let hashmap = read_scenario(content.as_bytes())?;
+        let mut buffer: Vec<u8> = Vec::new();
+        write_scenario(&hashmap, &mut buffer)?;
+
+        // Then
+        assert_eq!(
+            hashmap["V"],
+            vec![Element::RawTerrain(RawTerrain::new(
+                0,
+                1,
+                Terrain::Conveyor(Orientation::East)
+            ))]
+        );
+        assert_eq!(String::from_utf8(buffer).unwrap(), content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_write_portals_into_generated_map_file() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nP - 0 - 0 - 2 - 3\nA - Lara - 1 - 1 - S - 0\n";
+        let hashmap = read_scenario(content.as_bytes())?;
+        let temp_file = NamedTempFile::new()?;
+
+        // When
+        generate_map_file(&hashmap, temp_file.path().to_str().unwrap())?;
+
+        // Then
+        assert_eq!(
+            hashmap["P"],
+            vec![Element::RawPortal(RawPortal::new(0, 0, 2, 3))]
+        );
+        let mut result = String::new();
+        temp_file.reopen()?.read_to_string(&mut result)?;
+        assert_eq!(result, content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_write_carried_and_banked_treasure_when_map_has_banks() -> io::Result<()> {
+        // Given
+        let content = "C - 3 - 4\nB - 0 - 0\nA - Lara - 1 - 1 - S - AA - 1 - 2\n";
+        let mut hashmap = read_scenario(content.as_bytes())?;
+        if let Some(Element::RawAdventurer(adventurer)) = hashmap.get_mut("A").unwrap().first_mut()
+        {
+            adventurer.update_treasure();
+            adventurer.bank();
+            adventurer.update_treasure();
+        }
+        let mut buffer: Vec<u8> = Vec::new();
+
+        // When
+        write_result(&hashmap, &mut buffer)?;
 
-        std::fs::remove_file("test_map.txt")?;
+        // Then
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "C - 3 - 4\nB - 0 - 0\nA - Lara - 1 - 1 - S - 1:1 - 1 - 2\n"
+        );
+
+        Ok(())
+    }
+
+    /* SHOULD REJECT MALFORMED FILE */
+
+    fn parse(content: &[u8]) -> io::Result<HashMap<String, Vec<Element>>> {
+        read_scenario(content)
+    }
+
+    #[parameterized(line = {
+        "C",
+        "C - 3",
+        "C - x - 4",
+        "M - -1 - 2",
+        "T - 1 - 2",
+        "A - Lara - 0 - 0 - X - AA",
+        "A - Lara - 0 - 0 -  - AA",
+        "A - Lara - 0 - 0 - S",
+        "A - Lara - 0 - 0 - S - AA - 0",
+        "A - Lara - 0 - 0 - S - AA - 1/x",
+        "P - 1 - 2 - 3",
+        "Topology - sphere",
+        "Grid - triangle",
+        "A - Lara - 0 - 0 - SS - AA",
+        "Topology",
+        "B - 1",
+        "A - Lara - 0 - 0 - S - AA - 1 - x",
+        "W - 1",
+        "V - 1 - 2",
+        "V - 1 - 2 - X",
+        "A - Lara - 0 - 0 - S - script:ahead=wall?D",
+    })]
+    fn should_return_invalid_data_error_when_line_is_malformed(line: &str) {
+        // When
+        let result = parse(line.as_bytes());
+
+        // Then
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    /* PROPERTIES */
+
+    #[test]
+    fn should_never_panic_on_arbitrary_bytes() {
+        let alphabet = b"CMTABPWIV-0123456789 NSEOGD\n\xff";
+
+        for seed in 0..200 {
+            // Given
+            let mut rng = Rng::new(seed);
+            let length = rng.below(64);
+            let content: Vec<u8> = (0..length).map(|_| *rng.choose(alphabet)).collect();
+
+            // When + Then
+            let _ = parse(&content);
+        }
+    }
+
+    #[test]
+    fn should_read_back_every_generated_scenario() -> io::Result<()> {
+        for seed in 0..50 {
+            // Given
+            let scenario = generate(&random_options(seed)).unwrap();
+            let mut buffer: Vec<u8> = Vec::new();
+
+            // When
+            write_scenario(&scenario, &mut buffer)?;
+
+            // Then
+            assert_eq!(read_scenario(buffer.as_slice())?, scenario);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_back_map_of_every_generated_result() -> io::Result<()> {
+        for seed in 0..50 {
+            // Given
+            let scenario = generate(&random_options(seed)).unwrap();
+            let mut buffer: Vec<u8> = Vec::new();
+
+            // When
+            write_result(&scenario, &mut buffer)?;
+
+            // Then: the pattern field of a result holds the treasure
+            let result = read_scenario(buffer.as_slice())?;
+            for key in ["C", "M", "T"] {
+                assert_eq!(result.get(key), scenario.get(key));
+            }
+            let adventurers = |hashmap: &HashMap<String, Vec<Element>>| -> Vec<_> {
+                hashmap["A"]
+                    .iter()
+                    .map(|element| match element {
+                        Element::RawAdventurer(adventurer) => (
+                            adventurer.name.clone(),
+                            adventurer.position,
+                            adventurer.orientation,
+                        ),
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            };
+            assert_eq!(adventurers(&result), adventurers(&scenario));
+        }
 
         Ok(())
     }
@@ -0,0 +1,483 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::{
+    element::{Element, Orientation, RawAdventurer, RawMap, RawMountain, RawTreasure},
+    rng::Rng,
+};
+
+const ORIENTATIONS: [Orientation; 4] = [
+    Orientation::North,
+    Orientation::East,
+    Orientation::South,
+    Orientation::West,
+];
+const INSTRUCTIONS: [char; 3] = ['A', 'G', 'D'];
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Layout {
+    Random,
+    Maze,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "random" => Ok(Layout::Random),
+            "maze" => Ok(Layout::Maze),
+            _ => Err(format!("Unknown layout : {}", raw_value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub layout: Layout,
+    pub width: usize,
+    pub height: usize,
+    pub mountain_density: f64,
+    pub treasure_piles: usize,
+    pub pile_size: (usize, usize),
+    pub adventurers: usize,
+    pub pattern_length: usize,
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Random,
+            width: 8,
+            height: 8,
+            mountain_density: 0.15,
+            treasure_piles: 4,
+            pile_size: (1, 3),
+            adventurers: 2,
+            pattern_length: 16,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum GeneratorError {
+    NotEnoughCells { needed: usize, available: usize },
+    InvalidPileSize((usize, usize)),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorError::NotEnoughCells { needed, available } => write!(
+                f,
+                "{} cells are needed for treasures and adventurers but the map only has {}",
+                needed, available
+            ),
+            GeneratorError::InvalidPileSize((min, max)) => {
+                write!(f, "invalid pile size {}..{}", min, max)
+            }
+        }
+    }
+}
+
+/// Generates a scenario from `options.seed`. The same options always give
+/// the same scenario.
+///
+/// Every element gets its own cell, so the scenario passes validation.
+pub fn generate(
+    options: &GeneratorOptions,
+) -> Result<HashMap<String, Vec<Element>>, GeneratorError> {
+    let (min_pile, max_pile) = options.pile_size;
+    if min_pile == 0 || min_pile > max_pile {
+        return Err(GeneratorError::InvalidPileSize(options.pile_size));
+    }
+
+    let mut rng = Rng::new(options.seed);
+    let (mountains, treasure_cells, free_cells) = match options.layout {
+        Layout::Random => random_layout(options, &mut rng)?,
+        Layout::Maze => maze_layout(options, &mut rng)?,
+    };
+
+    let mut hashmap: HashMap<String, Vec<Element>> = HashMap::new();
+    hashmap.insert(
+        "C".to_string(),
+        vec![Element::RawMap(RawMap::new(options.width, options.height))],
+    );
+    hashmap.insert(
+        "M".to_string(),
+        mountains
+            .into_iter()
+            .map(|(x, y)| Element::RawMountain(RawMountain::new(x, y)))
+            .collect(),
+    );
+    hashmap.insert(
+        "T".to_string(),
+        treasure_cells
+            .into_iter()
+            .map(|(x, y)| {
+                Element::RawTreasure(RawTreasure::new(x, y, rng.between(min_pile, max_pile)))
+            })
+            .collect(),
+    );
+    hashmap.insert(
+        "A".to_string(),
+        free_cells
+            .into_iter()
+            .take(options.adventurers)
+            .enumerate()
+            .map(|(index, (x, y))| {
+                Element::RawAdventurer(RawAdventurer::new(
+                    format!("Adventurer{}", index + 1),
+                    x,
+                    y,
+                    *rng.choose(&ORIENTATIONS),
+                    random_pattern(&mut rng, options.pattern_length),
+                ))
+            })
+            .collect(),
+    );
+    hashmap.retain(|_, elements| !elements.is_empty());
+
+    Ok(hashmap)
+}
+
+type Layouts = (
+    Vec<(usize, usize)>,
+    Vec<(usize, usize)>,
+    Vec<(usize, usize)>,
+);
+
+/// Lays mountains at random with `options.mountain_density`, trimmed if they
+/// would leave no room for the treasures and adventurers.
+fn random_layout(options: &GeneratorOptions, rng: &mut Rng) -> Result<Layouts, GeneratorError> {
+    let available = options.width * options.height;
+    let needed = options.treasure_piles + options.adventurers;
+    if needed > available {
+        return Err(GeneratorError::NotEnoughCells { needed, available });
+    }
+
+    let mut cells: Vec<(usize, usize)> = (0..options.width)
+        .flat_map(|x| (0..options.height).map(move |y| (x, y)))
+        .collect();
+    rng.shuffle(&mut cells);
+
+    let mountains = cells
+        .iter()
+        .filter(|_| rng.chance(options.mountain_density))
+        .count()
+        .min(available - needed);
+    let mut treasures = cells.split_off(mountains);
+    let free = treasures.split_off(options.treasure_piles);
+
+    Ok((cells, treasures, free))
+}
+
+/// Carves a maze with a recursive backtracker: passages join the cells with
+/// even coordinates and every other cell is a mountain. The passages form a
+/// tree, so every treasure is reachable from every adventurer. Treasures go
+/// to dead ends first. `options.mountain_density` is not used.
+fn maze_layout(options: &GeneratorOptions, rng: &mut Rng) -> Result<Layouts, GeneratorError> {
+    let needed = options.treasure_piles + options.adventurers;
+    if options.width == 0 || options.height == 0 {
+        return Err(GeneratorError::NotEnoughCells {
+            needed,
+            available: 0,
+        });
+    }
+
+    let mut passable = vec![vec![false; options.height]; options.width];
+    let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+    passable[0][0] = true;
+
+    while let Some(&(x, y)) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = [(2, 0), (-2, 0), (0, 2), (0, -2)]
+            .iter()
+            .filter_map(|(dx, dy)| neighbour(options, (x, y), *dx, *dy))
+            .filter(|(nx, ny)| !passable[*nx][*ny])
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = *rng.choose(&unvisited);
+        passable[(x + next.0) / 2][(y + next.1) / 2] = true;
+        passable[next.0][next.1] = true;
+        stack.push(next);
+    }
+
+    let mut mountains = Vec::new();
+    let mut dead_ends = Vec::new();
+    let mut passages = Vec::new();
+    for x in 0..options.width {
+        for y in 0..options.height {
+            if !passable[x][y] {
+                mountains.push((x, y));
+                continue;
+            }
+
+            let exits = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .filter_map(|(dx, dy)| neighbour(options, (x, y), *dx, *dy))
+                .filter(|(nx, ny)| passable[*nx][*ny])
+                .count();
+            if exits == 1 {
+                dead_ends.push((x, y));
+            } else {
+                passages.push((x, y));
+            }
+        }
+    }
+
+    let available = dead_ends.len() + passages.len();
+    if needed > available {
+        return Err(GeneratorError::NotEnoughCells { needed, available });
+    }
+
+    rng.shuffle(&mut dead_ends);
+    rng.shuffle(&mut passages);
+    let mut treasures = dead_ends;
+    treasures.append(&mut passages);
+    let mut free = treasures.split_off(options.treasure_piles);
+    rng.shuffle(&mut free);
+
+    Ok((mountains, treasures, free))
+}
+
+fn neighbour(
+    options: &GeneratorOptions,
+    position: (usize, usize),
+    dx: isize,
+    dy: isize,
+) -> Option<(usize, usize)> {
+    let x = position.0.checked_add_signed(dx)?;
+    let y = position.1.checked_add_signed(dy)?;
+
+    (x < options.width && y < options.height).then_some((x, y))
+}
+
+fn random_pattern(rng: &mut Rng, length: usize) -> String {
+    (0..length).map(|_| *rng.choose(&INSTRUCTIONS)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashSet;
+
+    use crate::{simulation::Simulation, validation::validate};
+
+    use super::*;
+
+    /// Random but always satisfiable options, for property tests.
+    pub(crate) fn random_options(seed: u64) -> GeneratorOptions {
+        let mut rng = Rng::new(seed);
+        let width = rng.between(1, 8);
+        let height = rng.between(1, 8);
+        let treasure_piles = rng.below(width * height);
+
+        GeneratorOptions {
+            layout: Layout::Random,
+            width,
+            height,
+            mountain_density: rng.below(50) as f64 / 100.0,
+            treasure_piles,
+            pile_size: (1, rng.between(1, 4)),
+            adventurers: rng.between(1, width * height - treasure_piles),
+            pattern_length: rng.below(30),
+            seed,
+        }
+    }
+
+    fn count(hashmap: &HashMap<String, Vec<Element>>, key: &str) -> usize {
+        hashmap.get(key).map_or(0, |elements| elements.len())
+    }
+
+    /* SHOULD GENERATE */
+
+    #[test]
+    fn should_generate_same_scenario_for_same_seed() {
+        // Given
+        let options = GeneratorOptions {
+            seed: 12,
+            ..GeneratorOptions::default()
+        };
+
+        // When + Then
+        assert_eq!(generate(&options), generate(&options));
+    }
+
+    #[test]
+    fn should_generate_different_scenarios_for_different_seeds() {
+        // Given
+        let first = GeneratorOptions {
+            seed: 1,
+            ..GeneratorOptions::default()
+        };
+        let second = GeneratorOptions {
+            seed: 2,
+            ..GeneratorOptions::default()
+        };
+
+        // When + Then
+        assert_ne!(generate(&first), generate(&second));
+    }
+
+    #[test]
+    fn should_generate_valid_scenario_honouring_options() {
+        // Given
+        let options = GeneratorOptions {
+            width: 5,
+            height: 3,
+            treasure_piles: 3,
+            pile_size: (2, 4),
+            adventurers: 2,
+            pattern_length: 7,
+            seed: 5,
+            ..GeneratorOptions::default()
+        };
+
+        // When
+        let scenario = generate(&options).unwrap();
+
+        // Then
+        assert!(validate(&scenario).is_empty());
+        assert_eq!(scenario["C"], vec![Element::RawMap(RawMap::new(5, 3))]);
+        assert_eq!(count(&scenario, "T"), 3);
+        assert_eq!(count(&scenario, "A"), 2);
+        for element in scenario["T"].iter() {
+            if let Element::RawTreasure(treasure) = element {
+                assert!((2..=4).contains(&treasure.nb_treasure));
+            }
+        }
+        for element in scenario["A"].iter() {
+            if let Element::RawAdventurer(adventurer) = element {
+                assert_eq!(adventurer.pattern.len(), 7);
+            }
+        }
+    }
+
+    #[test]
+    fn should_leave_room_for_treasures_and_adventurers_on_dense_map() {
+        // Given
+        let options = GeneratorOptions {
+            width: 2,
+            height: 2,
+            mountain_density: 1.0,
+            treasure_piles: 1,
+            adventurers: 1,
+            ..GeneratorOptions::default()
+        };
+
+        // When
+        let scenario = generate(&options).unwrap();
+
+        // Then
+        assert_eq!(count(&scenario, "M"), 2);
+        let positions: HashSet<_> = scenario
+            .values()
+            .flatten()
+            .filter(|element| !matches!(element, Element::RawMap(_)))
+            .map(|element| element.position())
+            .collect();
+        assert_eq!(positions.len(), 4);
+    }
+
+    /* SHOULD GENERATE MAZE */
+
+    fn generate_maze(seed: u64, treasure_piles: usize) -> HashMap<String, Vec<Element>> {
+        let options = GeneratorOptions {
+            layout: Layout::Maze,
+            width: 9,
+            height: 7,
+            treasure_piles,
+            adventurers: 3,
+            seed,
+            ..GeneratorOptions::default()
+        };
+
+        generate(&options).unwrap()
+    }
+
+    #[test]
+    fn should_generate_valid_maze_with_every_treasure_reachable_from_every_adventurer() {
+        for seed in 0..20 {
+            // Given + When
+            let scenario = generate_maze(seed, 4);
+            let simulation = Simulation::from_hashmap(&scenario);
+
+            // Then
+            assert!(validate(&scenario).is_empty());
+            for adventurer in simulation.adventurers.iter() {
+                let report = simulation.map.analyse_reachability(&[adventurer.position]);
+                assert!(report.unreachable_treasures.is_empty());
+                assert!(report.unreachable_plains.is_empty());
+                assert_eq!(report.components.len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn should_place_treasures_in_dead_ends_of_maze() {
+        // Given + When: a tree always has at least two leaves
+        let scenario = generate_maze(3, 2);
+        let the_map = Simulation::from_hashmap(&scenario).map;
+
+        // Then
+        for element in scenario["T"].iter() {
+            let position = element.position().unwrap();
+            let exits = the_map
+                .neighbours(position)
+                .into_iter()
+                .filter(|neighbour| the_map.is_passable(*neighbour))
+                .count();
+            assert_eq!(exits, 1);
+        }
+    }
+
+    #[test]
+    fn should_parse_layout() {
+        assert_eq!("maze".parse(), Ok(Layout::Maze));
+        assert_eq!("random".parse(), Ok(Layout::Random));
+        assert!("spiral".parse::<Layout>().is_err());
+    }
+
+    /* SHOULD FAIL */
+
+    #[test]
+    fn should_fail_when_map_is_too_small() {
+        // Given
+        let options = GeneratorOptions {
+            width: 1,
+            height: 2,
+            treasure_piles: 2,
+            adventurers: 1,
+            ..GeneratorOptions::default()
+        };
+
+        // When + Then
+        assert_eq!(
+            generate(&options),
+            Err(GeneratorError::NotEnoughCells {
+                needed: 3,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn should_fail_when_pile_size_is_empty() {
+        // Given
+        let options = GeneratorOptions {
+            pile_size: (3, 1),
+            ..GeneratorOptions::default()
+        };
+
+        // When + Then
+        assert_eq!(
+            generate(&options),
+            Err(GeneratorError::InvalidPileSize((3, 1)))
+        );
+    }
+}
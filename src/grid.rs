@@ -0,0 +1,249 @@
+use std::str::FromStr;
+
+use crate::element::Orientation;
+
+/// Shape of the cells of a map: which orientations exist, in which cell
+/// each of them leads and how far apart two cells are.
+pub trait Geometry {
+    /// Orientations of the grid in clockwise order.
+    fn orientations(&self) -> &'static [Orientation];
+
+    /// Cell offset of an advance towards `orientation`, if the orientation
+    /// exists on the grid.
+    fn offset(&self, orientation: Orientation) -> Option<(isize, isize)>;
+
+    /// Number of steps needed to move of `(dx, dy)` on an empty grid.
+    fn distance(&self, dx: isize, dy: isize) -> usize;
+
+    /// Offsets of the cells crossed by a straight line from a cell to the one
+    /// at `(dx, dy)` from it, both included, in order.
+    fn line(&self, dx: isize, dy: isize) -> Vec<(isize, isize)>;
+
+    /// Orientation after a turn of one step, clockwise or not. An orientation
+    /// that does not exist on the grid is kept.
+    fn turn(&self, orientation: Orientation, clockwise: bool) -> Orientation {
+        let orientations = self.orientations();
+        let Some(index) = orientations.iter().position(|other| *other == orientation) else {
+            return orientation;
+        };
+        let step = if clockwise { 1 } else { orientations.len() - 1 };
+
+        orientations[(index + step) % orientations.len()]
+    }
+}
+
+/// Square cells with four orientations.
+pub struct SquareGeometry;
+
+impl Geometry for SquareGeometry {
+    fn orientations(&self) -> &'static [Orientation] {
+        &[
+            Orientation::North,
+            Orientation::East,
+            Orientation::South,
+            Orientation::West,
+        ]
+    }
+
+    fn offset(&self, orientation: Orientation) -> Option<(isize, isize)> {
+        match orientation {
+            Orientation::North => Some((0, -1)),
+            Orientation::East => Some((1, 0)),
+            Orientation::South => Some((0, 1)),
+            Orientation::West => Some((-1, 0)),
+            _ => None,
+        }
+    }
+
+    fn distance(&self, dx: isize, dy: isize) -> usize {
+        dx.unsigned_abs() + dy.unsigned_abs()
+    }
+
+    fn line(&self, dx: isize, dy: isize) -> Vec<(isize, isize)> {
+        let steps = dx.unsigned_abs().max(dy.unsigned_abs());
+
+        (0..=steps)
+            .map(|step| {
+                let (x, y) = lerp(dx, dy, step, steps);
+                (x.round() as isize, y.round() as isize)
+            })
+            .collect()
+    }
+}
+
+/// Flat-topped hexagonal cells with six orientations, in axial coordinates:
+/// `x` is the column and `y` goes down the column, so north and south are
+/// the same as on a square grid.
+pub struct HexGeometry;
+
+impl Geometry for HexGeometry {
+    fn orientations(&self) -> &'static [Orientation] {
+        &[
+            Orientation::North,
+            Orientation::NorthEast,
+            Orientation::SouthEast,
+            Orientation::South,
+            Orientation::SouthWest,
+            Orientation::NorthWest,
+        ]
+    }
+
+    fn offset(&self, orientation: Orientation) -> Option<(isize, isize)> {
+        match orientation {
+            Orientation::North => Some((0, -1)),
+            Orientation::NorthEast => Some((1, -1)),
+            Orientation::SouthEast => Some((1, 0)),
+            Orientation::South => Some((0, 1)),
+            Orientation::SouthWest => Some((-1, 1)),
+            Orientation::NorthWest => Some((-1, 0)),
+            _ => None,
+        }
+    }
+
+    fn distance(&self, dx: isize, dy: isize) -> usize {
+        (dx.unsigned_abs() + dy.unsigned_abs() + (dx + dy).unsigned_abs()) / 2
+    }
+
+    /// Rounds the points of the line in cube coordinates `(x, y, -x - y)`,
+    /// fixing the coordinate that rounded the furthest from its point.
+    fn line(&self, dx: isize, dy: isize) -> Vec<(isize, isize)> {
+        let steps = self.distance(dx, dy);
+
+        (0..=steps)
+            .map(|step| {
+                let (x, y) = lerp(dx, dy, step, steps);
+                let z = -x - y;
+                let (mut round_x, mut round_y, round_z) = (x.round(), y.round(), z.round());
+                let (error_x, error_y, error_z) = (
+                    (round_x - x).abs(),
+                    (round_y - y).abs(),
+                    (round_z - z).abs(),
+                );
+                if error_x > error_y && error_x > error_z {
+                    round_x = -round_y - round_z;
+                } else if error_y > error_z {
+                    round_y = -round_x - round_z;
+                }
+
+                (round_x as isize, round_y as isize)
+            })
+            .collect()
+    }
+}
+
+/// Point `step / steps` of the way to `(dx, dy)`, nudged off the cell
+/// borders so that lines along them always round the same way.
+fn lerp(dx: isize, dy: isize, step: usize, steps: usize) -> (f64, f64) {
+    let ratio = if steps == 0 {
+        0.0
+    } else {
+        step as f64 / steps as f64
+    };
+
+    (dx as f64 * ratio + 1e-6, dy as f64 * ratio + 2e-6)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Grid {
+    #[default]
+    Square,
+    Hex,
+}
+
+impl Grid {
+    pub fn geometry(&self) -> &'static dyn Geometry {
+        match self {
+            Grid::Square => &SquareGeometry,
+            Grid::Hex => &HexGeometry,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grid::Square => "square",
+            Grid::Hex => "hex",
+        }
+    }
+}
+
+impl FromStr for Grid {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "square" => Ok(Grid::Square),
+            "hex" => Ok(Grid::Hex),
+            _ => Err(format!("Unknown grid : {}", raw_value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parameterized::parameterized;
+
+    use super::*;
+
+    #[parameterized(
+        grid = { Grid::Square, Grid::Square, Grid::Hex, Grid::Hex },
+        clockwise = { true, false, true, false },
+        expected = { Orientation::East, Orientation::West, Orientation::NorthEast, Orientation::NorthWest })]
+    fn should_turn_one_step_from_north(grid: Grid, clockwise: bool, expected: Orientation) {
+        assert_eq!(
+            grid.geometry().turn(Orientation::North, clockwise),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_come_back_after_full_turn() {
+        for grid in [Grid::Square, Grid::Hex] {
+            // Given
+            let geometry = grid.geometry();
+
+            // When
+            let orientation = geometry
+                .orientations()
+                .iter()
+                .fold(Orientation::South, |orientation, _| {
+                    geometry.turn(orientation, true)
+                });
+
+            // Then
+            assert_eq!(orientation, Orientation::South);
+        }
+    }
+
+    #[test]
+    fn should_have_no_offset_for_orientation_of_other_grid() {
+        assert_eq!(SquareGeometry.offset(Orientation::NorthEast), None);
+        assert_eq!(HexGeometry.offset(Orientation::East), None);
+    }
+
+    #[parameterized(
+        dx = { 2, 2, -1, 0 },
+        dy = { -2, 0, 2, 0 },
+        expected = { 2, 2, 2, 0 })]
+    fn should_measure_hex_distance(dx: isize, dy: isize, expected: usize) {
+        assert_eq!(HexGeometry.distance(dx, dy), expected);
+    }
+
+    #[parameterized(
+        grid = { Grid::Square, Grid::Square, Grid::Hex, Grid::Hex },
+        dx = { 3, 2, 2, 0 },
+        dy = { 1, -2, -1, 0 },
+        expected = {
+            vec![(0, 0), (1, 0), (2, 1), (3, 1)],
+            vec![(0, 0), (1, -1), (2, -2)],
+            vec![(0, 0), (1, 0), (2, -1)],
+            vec![(0, 0)]
+        })]
+    fn should_trace_line_between_cells(
+        grid: Grid,
+        dx: isize,
+        dy: isize,
+        expected: Vec<(isize, isize)>,
+    ) {
+        assert_eq!(grid.geometry().line(dx, dy), expected);
+    }
+}
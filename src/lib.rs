@@ -0,0 +1,20 @@
+pub mod batch;
+pub mod controller;
+pub mod element;
+pub mod environment;
+pub mod file;
+pub mod generator;
+pub mod grid;
+pub mod lint;
+pub mod map;
+pub mod observation;
+pub mod planner;
+pub mod reachability;
+pub mod render;
+pub mod report;
+pub mod rng;
+pub mod script;
+pub mod simulation;
+pub mod storage;
+pub mod tournament;
+pub mod validation;
@@ -0,0 +1,511 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    element::{Element, Orientation, RawAdventurer},
+    map::Map,
+    simulation::{next_move, Simulation},
+};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LintKind {
+    FullTurn,
+    CancellingTurns,
+    BlockedAdvance,
+    Trailing,
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            LintKind::FullTurn => "a full turn in the same direction has no effect, remove it",
+            LintKind::CancellingTurns => "opposite turns cancel each other, remove them",
+            LintKind::BlockedAdvance => {
+                "advance always hits a mountain or the edge of the map, remove it"
+            }
+            LintKind::Trailing => "no treasure can be collected from here, remove the rest",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+/// An instruction range of a pattern that has no effect, `offset` and
+/// `length` being counted in characters of the original pattern.
+#[derive(PartialEq, Debug)]
+pub struct LintSuggestion {
+    pub offset: usize,
+    pub length: usize,
+    pub kind: LintKind,
+}
+
+/// Lints the pattern of every adventurer of the simulation, in declaration
+/// order.
+pub fn lint_simulation(simulation: &Simulation) -> Vec<(String, Vec<LintSuggestion>)> {
+    let can_be_blocked = simulation.adventurers.len() > 1;
+
+    simulation
+        .adventurers
+        .iter()
+        .map(|adventurer| {
+            (
+                adventurer.name.clone(),
+                lint_pattern(&simulation.map, adventurer, can_be_blocked),
+            )
+        })
+        .collect()
+}
+
+/// Flags the instructions of `adventurer.pattern` that have no effect. On a
+/// map with banks, dropping carried treasure on a bank scores too.
+///
+/// Advances follow the moves of the game, through portals, over ice and
+/// along conveyors. Mountains and edges never move, but other adventurers
+/// may block an advance, so when `can_be_blocked` is set each advance may
+/// stop before any of the cells it enters. The linter follows every position and orientation the adventurer
+/// can be in and only flags what holds for all of them.
+pub fn lint_pattern(
+    the_map: &Map,
+    adventurer: &RawAdventurer,
+    can_be_blocked: bool,
+) -> Vec<LintSuggestion> {
+    let actions: Vec<char> = adventurer.pattern.chars().collect();
+    let mut suggestions = lint_turns(&actions, the_map.geometry().orientations().len());
+
+    // Whether the adventurer may carry treasure matters once banks score
+    let has_banks = the_map.has_banks();
+    let mut states: HashSet<((usize, usize), Orientation, bool)> = HashSet::from([(
+        adventurer.position,
+        adventurer.orientation,
+        adventurer.treasure > 0,
+    )]);
+    let mut last_collect: Option<usize> = None;
+
+    for (offset, action) in actions.iter().enumerate() {
+        let mut next_states = HashSet::new();
+        let mut always_blocked = *action == 'A';
+
+        for (position, orientation, carrying) in states.iter() {
+            let (dx, dy, new_orientation) = next_move(the_map.geometry(), *orientation, *action);
+            let path = the_map.path(*position, dx, dy);
+            let Some(stop) = path.last() else {
+                next_states.insert((*position, new_orientation, *carrying));
+                continue;
+            };
+
+            always_blocked = false;
+            if can_be_blocked {
+                // Another adventurer may stop the move before any cell of it
+                next_states.insert((*position, new_orientation, *carrying));
+            }
+            let mut carrying = *carrying;
+            for cell in path.iter() {
+                if has_treasure(the_map, *cell) {
+                    last_collect = Some(offset);
+                    carrying = true;
+                } else if has_banks && carrying && is_bank(the_map, *cell) {
+                    last_collect = Some(offset);
+                    carrying = false;
+                }
+                if can_be_blocked {
+                    next_states.insert((*cell, new_orientation, carrying));
+                }
+            }
+            next_states.insert((*stop, new_orientation, carrying));
+        }
+
+        if always_blocked {
+            suggestions.push(LintSuggestion {
+                offset,
+                length: 1,
+                kind: LintKind::BlockedAdvance,
+            });
+        }
+        states = next_states;
+    }
+
+    let trailing_start = last_collect.map_or(0, |offset| offset + 1);
+    if trailing_start < actions.len() {
+        suggestions.push(LintSuggestion {
+            offset: trailing_start,
+            length: actions.len() - trailing_start,
+            kind: LintKind::Trailing,
+        });
+    }
+
+    suggestions.sort_by_key(|suggestion| suggestion.offset);
+    suggestions
+}
+
+/// Flags full turns, made of `full_turn` turns in the same direction, and
+/// opposite turns that cancel each other.
+fn lint_turns(actions: &[char], full_turn: usize) -> Vec<LintSuggestion> {
+    let mut suggestions = Vec::new();
+    let mut offset = 0;
+
+    while offset < actions.len() {
+        let action = actions[offset];
+        let run = actions[offset..]
+            .iter()
+            .take_while(|other| **other == action)
+            .count();
+
+        if action == 'G' || action == 'D' {
+            for index in 0..run / full_turn {
+                suggestions.push(LintSuggestion {
+                    offset: offset + index * full_turn,
+                    length: full_turn,
+                    kind: LintKind::FullTurn,
+                });
+            }
+
+            let next = offset + run;
+            let next_run = actions[next..]
+                .iter()
+                .take_while(|other| Some(*other) == actions.get(next))
+                .count();
+            if run % full_turn != 0
+                && next_run % full_turn != 0
+                && is_opposite_turn(action, actions[next])
+            {
+                suggestions.push(LintSuggestion {
+                    offset: next - 1,
+                    length: 2,
+                    kind: LintKind::CancellingTurns,
+                });
+            }
+        }
+
+        offset += run;
+    }
+
+    suggestions
+}
+
+fn is_opposite_turn(first: char, second: char) -> bool {
+    matches!((first, second), ('G', 'D') | ('D', 'G'))
+}
+
+fn is_bank(the_map: &Map, position: (usize, usize)) -> bool {
+    matches!(the_map.element_at(position), Some(Element::RawBank(_)))
+}
+
+fn has_treasure(the_map: &Map, position: (usize, usize)) -> bool {
+    matches!(
+        the_map.element_at(position),
+        Some(Element::RawTreasure(treasure)) if treasure.nb_treasure > 0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use crate::{
+        element::{RawBank, RawMountain, RawPortal, RawTerrain, RawTreasure, Terrain},
+        grid::Grid,
+    };
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    fn init_mock_map() -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(1, 1))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 2, 1))],
+        );
+
+        Map::new(3, 4, hash_map)
+    }
+
+    fn lint(pattern: &str, can_be_blocked: bool) -> Vec<LintSuggestion> {
+        let adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            pattern.to_string(),
+        );
+
+        lint_pattern(&init_mock_map(), &adventurer, can_be_blocked)
+    }
+
+    fn lint_east(the_map: &Map, pattern: &str) -> Vec<LintSuggestion> {
+        let adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::East,
+            pattern.to_string(),
+        );
+
+        lint_pattern(the_map, &adventurer, false)
+    }
+
+    /* SHOULD FIND NOTHING */
+
+    #[test]
+    fn should_not_flag_pattern_collecting_treasure_at_the_end() {
+        assert_eq!(lint("AA", false), vec![]);
+    }
+
+    /* SHOULD FLAG TURNS */
+
+    #[parameterized(
+        pattern = { "GGGGAA", "DDDDAA", "GDAA", "DGAA" },
+        expected = {
+            (0, 4, LintKind::FullTurn),
+            (0, 4, LintKind::FullTurn),
+            (0, 2, LintKind::CancellingTurns),
+            (0, 2, LintKind::CancellingTurns),
+        })]
+    fn should_flag_turns_without_effect(pattern: &str, expected: (usize, usize, LintKind)) {
+        assert_eq!(
+            lint(pattern, false),
+            vec![LintSuggestion {
+                offset: expected.0,
+                length: expected.1,
+                kind: expected.2,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_flag_cancelling_turns_at_end_of_turn_run() {
+        assert_eq!(
+            lint("GGDDAA", false),
+            vec![LintSuggestion {
+                offset: 1,
+                length: 2,
+                kind: LintKind::CancellingTurns,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_flag_six_turns_as_full_turn_on_hex_grid() {
+        // Given
+        let mut the_map = init_mock_map();
+        the_map.grid = Grid::Hex;
+        let adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            "DDDDDDAA".to_string(),
+        );
+
+        // When
+        let result = lint_pattern(&the_map, &adventurer, false);
+
+        // Then
+        assert_eq!(
+            result,
+            vec![LintSuggestion {
+                offset: 0,
+                length: 6,
+                kind: LintKind::FullTurn,
+            }]
+        );
+    }
+
+    /* SHOULD FLAG BLOCKED ADVANCES */
+
+    #[test]
+    fn should_flag_advance_into_edge_and_mountain() {
+        // Given: west of (0, 0) is the edge, south of (1, 0) is the mountain
+        let pattern = "DAGGADA";
+
+        // When
+        let result = lint(pattern, false);
+
+        // Then
+        let blocked: Vec<usize> = result
+            .iter()
+            .filter(|suggestion| suggestion.kind == LintKind::BlockedAdvance)
+            .map(|suggestion| suggestion.offset)
+            .collect();
+        assert_eq!(blocked, vec![1, 6]);
+    }
+
+    #[test]
+    fn should_not_flag_advance_that_may_succeed_when_blocked_earlier() {
+        // Given: the mountain is only ahead if the first advance succeeded
+        let pattern = "AGA";
+
+        // When
+        let result = lint(pattern, true);
+
+        // Then
+        assert!(result
+            .iter()
+            .all(|suggestion| suggestion.kind != LintKind::BlockedAdvance));
+    }
+
+    /* SHOULD FLAG TRAILING INSTRUCTIONS */
+
+    #[test]
+    fn should_flag_instructions_after_last_possible_collect() {
+        assert_eq!(
+            lint("AAGA", false),
+            vec![LintSuggestion {
+                offset: 2,
+                length: 2,
+                kind: LintKind::Trailing,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_flag_whole_pattern_when_nothing_can_be_collected() {
+        assert_eq!(
+            lint("GA", false),
+            vec![LintSuggestion {
+                offset: 0,
+                length: 2,
+                kind: LintKind::Trailing,
+            }]
+        );
+    }
+
+    #[test]
+    fn should_not_flag_trip_back_to_bank() {
+        // Given: B L T
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("B".to_string(), vec![Element::RawBank(RawBank::new(0, 0))]);
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(2, 0, 1))],
+        );
+        let the_map = Map::new(3, 1, hash_map);
+        let adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            1,
+            0,
+            Orientation::East,
+            "AGGAA".to_string(),
+        );
+
+        // When
+        let result = lint_pattern(&the_map, &adventurer, false);
+
+        // Then
+        assert_eq!(result, vec![]);
+    }
+
+    /* SHOULD FOLLOW MOVES */
+
+    #[test]
+    fn should_collect_treasure_at_end_of_ice_slide() {
+        // Given: L = = T
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "I".to_string(),
+            vec![
+                Element::RawTerrain(RawTerrain::new(1, 0, Terrain::Ice)),
+                Element::RawTerrain(RawTerrain::new(2, 0, Terrain::Ice)),
+            ],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(3, 0, 1))],
+        );
+        let the_map = Map::new(4, 1, hash_map);
+
+        // When
+        let result = lint_east(&the_map, "AA");
+
+        // Then: the slide ends on the treasure, against the edge
+        assert_eq!(
+            result,
+            vec![
+                LintSuggestion {
+                    offset: 1,
+                    length: 1,
+                    kind: LintKind::BlockedAdvance,
+                },
+                LintSuggestion {
+                    offset: 1,
+                    length: 1,
+                    kind: LintKind::Trailing,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_flag_advance_against_conveyor() {
+        // Given: L < T
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "V".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(
+                1,
+                0,
+                Terrain::Conveyor(Orientation::West),
+            ))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(2, 0, 1))],
+        );
+        let the_map = Map::new(3, 1, hash_map);
+
+        // When
+        let result = lint_east(&the_map, "A");
+
+        // Then
+        assert_eq!(
+            result,
+            vec![
+                LintSuggestion {
+                    offset: 0,
+                    length: 1,
+                    kind: LintKind::BlockedAdvance,
+                },
+                LintSuggestion {
+                    offset: 0,
+                    length: 1,
+                    kind: LintKind::Trailing,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_collect_treasure_behind_portal() {
+        // Given
+        // L P .
+        // M M M
+        // T P .
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            (0..3)
+                .map(|x| Element::RawMountain(RawMountain::new(x, 1)))
+                .collect(),
+        );
+        hash_map.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(1, 0, 1, 2))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 2, 1))],
+        );
+        let the_map = Map::new(3, 3, hash_map);
+
+        // When
+        let result = lint_east(&the_map, "AGGA");
+
+        // Then
+        assert_eq!(result, vec![]);
+    }
+}
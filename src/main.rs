@@ -1,458 +1,460 @@
-use std::{collections::HashMap, fs::File, io};
-
-use crate::{
-    element::{Element, Orientation, RawAdventurer, RawMap},
-    file::generate_map_file,
-    map::Map,
+use std::{
+    collections::HashMap,
+    env,
+    fs::File,
+    io::{self, Write},
+    process,
+    time::Duration,
 };
 
-mod element;
-mod file;
-mod map;
+use treasure_map::{
+    batch::{self, BatchOptions},
+    controller::ControllerKind,
+    element::Element,
+    file::{self, write_result, write_scenario},
+    generator::{self, GeneratorOptions},
+    lint,
+    observation::{Observation, Sight},
+    planner::{self, PlannerOptions},
+    render,
+    report::{Report, ReportFormat},
+    script,
+    simulation::{ConflictPolicy, ResolutionMode, RunOutcome, Simulation},
+    tournament::{self, Tournament, TournamentFormat, TournamentOptions},
+    validation,
+};
 
 static INPUT_FILE_PATH: &str = "src/files/exercise.txt";
 static OUTPUT_FILE_PATH: &str = "src/files/result.txt";
-
-fn update_position(
-    the_map: &mut Map,
-    adventurer: RawAdventurer,
-    new_x: isize,
-    new_y: isize,
-) -> RawAdventurer {
-    let updated_adventurer = &adventurer.update_position(new_x, new_y);
-
-    if updated_adventurer.position.0 >= the_map.rows
-        || updated_adventurer.position.1 >= the_map.cols
-    {
-        return adventurer.clone();
+static GENERATED_FILE_PATH: &str = "src/files/generated.txt";
+// Reads from stdin or writes to stdout instead of a file
+static STANDARD_STREAM: &str = "-";
+// Options taking no value
+static SWITCHES: [&str; 2] = ["--loop", "--line-of-sight"];
+
+fn load_scenario(path: &str) -> io::Result<HashMap<String, Vec<Element>>> {
+    if path == STANDARD_STREAM {
+        return file::read_scenario(io::stdin().lock());
     }
 
-    match the_map.elements[updated_adventurer.position.0][updated_adventurer.position.1] {
-        Element::RawMountain(_) => adventurer.clone(),
-        Element::RawTreasure(_) => {
-            Map::update_position(
-                the_map,
-                updated_adventurer.position.0,
-                updated_adventurer.position.1,
-            );
-            updated_adventurer.update_treasure()
-        }
-        _ => updated_adventurer.clone(),
-    }
+    let file = File::open(path)?;
+
+    file::file_to_hashmap(file)
 }
 
-fn get_sequence(pattern: &str) -> (char, Vec<char>) {
-    let chars: Vec<char> = pattern.chars().collect();
-    if let Some((first, rest)) = chars.split_first() {
-        return (*first, rest.to_vec());
+fn create_output(path: &str) -> io::Result<Box<dyn Write>> {
+    if path == STANDARD_STREAM {
+        return Ok(Box::new(io::stdout().lock()));
     }
 
-    panic!("Input pattern is empty");
+    Ok(Box::new(File::create(path)?))
 }
 
-fn update_adventurer(adventurer: RawAdventurer, the_map: &mut Map) -> RawAdventurer {
-    let pattern = adventurer.pattern.clone();
-    let (action, rest_action) = get_sequence(&pattern);
-
-    let (dx, dy, new_orientation) = match adventurer.orientation {
-        Orientation::North => match action {
-            'A' => (0, -1, adventurer.orientation),
-            'G' => (0, 0, Orientation::West),
-            'D' => (0, 0, Orientation::East),
-            _ => (0, 0, adventurer.orientation),
-        },
-        Orientation::South => match action {
-            'A' => (0, 1, adventurer.orientation),
-            'G' => (0, 0, Orientation::East),
-            'D' => (0, 0, Orientation::West),
-            _ => (0, 0, adventurer.orientation),
-        },
-        Orientation::West => match action {
-            'A' => (-1, 0, adventurer.orientation),
-            'G' => (0, 0, Orientation::South),
-            'D' => (0, 0, Orientation::North),
-            _ => (0, 0, adventurer.orientation),
-        },
-        Orientation::East => match action {
-            'A' => (1, 0, adventurer.orientation),
-            'G' => (0, 0, Orientation::North),
-            'D' => (0, 0, Orientation::South),
-            _ => (0, 0, adventurer.orientation),
-        },
-    };
-
-    RawAdventurer {
-        orientation: new_orientation,
-        pattern: rest_action.iter().cloned().collect::<String>(),
-        ..update_position(the_map, adventurer.clone(), dx, dy)
-    }
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
 }
 
-fn main() -> io::Result<()> {
-    let file = File::open(INPUT_FILE_PATH)?;
-
-    let map: HashMap<String, Vec<Element>> = file::file_to_hashmap(file).unwrap();
-
-    // Get map
-    let map_size: Vec<RawMap> = map
-        .get("C")
-        .and_then(|raw_maps| {
-            Some(
-                raw_maps
-                    .iter()
-                    .filter_map(|element| {
-                        if let Element::RawMap(raw_map) = element {
-                            Some(raw_map.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .unwrap_or_else(|| panic!("No map size found"));
-
-    // Get adventurers
-    let mut raw_adventurers: Vec<RawAdventurer> = map
-        .get("A")
-        .and_then(|adventurers| {
-            Some(
-                adventurers
-                    .iter()
-                    .filter_map(|element| {
-                        if let Element::RawAdventurer(adventurer) = element {
-                            Some(adventurer.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .unwrap_or_else(|| panic!("No adventurers found"));
-
-    let mut the_map = map::Map::new(map_size[0].position.0, map_size[0].position.1, map.clone());
-
-    Map::print_map(&the_map.elements);
-
-    /* ITERATION ADVENTURER */
-
-    loop {
-        let mut all_done = true;
-
-        for i in 0..raw_adventurers.len() {
-            if !raw_adventurers[i].pattern.is_empty() {
-                all_done = false;
-                raw_adventurers[i] = update_adventurer(raw_adventurers[i].clone(), &mut the_map);
-            }
-        }
+/// Values of an option given several times, in order.
+fn option_values<'a>(args: &'a [String], name: &str) -> Vec<&'a str> {
+    args.iter()
+        .zip(args.iter().skip(1))
+        .filter(|(arg, _)| *arg == name)
+        .map(|(_, value)| value.as_str())
+        .collect()
+}
 
-        if all_done {
-            break;
+fn positional_args(args: &[String]) -> Vec<&str> {
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if SWITCHES.contains(&arg.as_str()) {
+            continue;
+        } else if arg.starts_with("--") {
+            iter.next();
+        } else {
+            positional.push(arg.as_str());
         }
     }
 
-    Map::print_map(&the_map.elements);
-    let _ = generate_map_file(&the_map.to_hashmap(raw_adventurers), OUTPUT_FILE_PATH);
-
-    Ok(())
+    positional
 }
 
-#[cfg(test)]
-mod tests {
-
-    /* SHOULD ADVENTURER MOVE */
-
-    use std::collections::HashMap;
+fn parse_option<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> T {
+    option_value(args, name)
+        .map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for {} : {}", name, value))
+        })
+        .unwrap_or(default)
+}
 
-    use parameterized::parameterized;
+fn parse_resolution(args: &[String]) -> ResolutionMode {
+    let conflict = parse_option(args, "--conflict", ConflictPolicy::BothBlocked);
 
-    use crate::{
-        element::{Element, Orientation, RawAdventurer, RawMap, RawMountain, RawTreasure},
-        get_sequence,
-        map::Map,
-        update_adventurer, update_position,
-    };
+    match option_value(args, "--resolution").unwrap_or("sequential") {
+        "sequential" => ResolutionMode::Sequential,
+        "simultaneous" => ResolutionMode::Simultaneous(conflict),
+        other => panic!("Invalid value for --resolution : {}", other),
+    }
+}
 
-    const ROW_SIZE_MAP: usize = 3;
-    const COL_SIZE_MAP: usize = 4;
-
-    fn init_mock_map() -> Map {
-        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
-        hash_map.insert(
-            "M".to_string(),
-            vec![
-                Element::RawMap(RawMap::new(4, 4)),
-                Element::RawAdventurer(RawAdventurer::new(
-                    "Lara".to_string(),
-                    4,
-                    4,
-                    Orientation::South,
-                    "AA".to_string(),
-                )),
-                Element::RawTreasure(RawTreasure::new(0, 1, 2)),
-                Element::RawMountain(RawMountain::new(2, 3)),
-                Element::RawMountain(RawMountain::new(1, 1)),
-            ],
-        );
+/// Gives the adventurers named by `--controller Name=kind` that controller.
+fn apply_controllers(args: &[String], content: &mut HashMap<String, Vec<Element>>) {
+    for value in option_values(args, "--controller") {
+        let (name, kind) = value
+            .split_once('=')
+            .unwrap_or_else(|| panic!("Invalid value for --controller : {}", value));
+        let kind: ControllerKind = kind
+            .parse()
+            .unwrap_or_else(|error| panic!("Invalid value for --controller : {}", error));
+        let adventurer = content
+            .values_mut()
+            .flatten()
+            .find_map(|element| match element {
+                Element::RawAdventurer(adventurer) if adventurer.name == name => Some(adventurer),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("Unknown adventurer for --controller : {}", name));
+        adventurer.controller = kind;
+    }
+}
 
-        Map::new(ROW_SIZE_MAP, COL_SIZE_MAP, hash_map)
+fn run(args: &[String]) -> io::Result<()> {
+    let positional = positional_args(args);
+    let input = positional.first().copied().unwrap_or(INPUT_FILE_PATH);
+    let output = positional.get(1).copied().unwrap_or(OUTPUT_FILE_PATH);
+
+    let mut map = load_scenario(input)?;
+    apply_controllers(args, &mut map);
+    let max_turns = option_value(args, "--max-turns").map(|_| parse_option(args, "--max-turns", 0));
+    let mut simulation = Simulation::from_hashmap(&map)
+        .with_resolution(parse_resolution(args))
+        .with_looping(args.iter().any(|arg| arg == "--loop"))
+        .with_max_turns(max_turns)
+        .with_script_budget(parse_option(
+            args,
+            "--script-budget",
+            script::DEFAULT_BUDGET,
+        ))
+        .with_sight(Sight {
+            radius: parse_option(args, "--sight", Sight::default().radius),
+            line_of_sight: args.iter().any(|arg| arg == "--line-of-sight"),
+        });
+    let verbose = output != STANDARD_STREAM;
+    let fog = option_value(args, "--fog");
+
+    if verbose {
+        draw(&simulation, fog);
     }
 
-    #[test]
-    fn should_adventurer_move_to_next_position() {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer =
-            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string());
+    /* ITERATION ADVENTURER */
 
-        // When
-        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 1, 0);
+    match simulation.run() {
+        RunOutcome::Finished => (),
+        RunOutcome::CycleDetected { period, first_turn } => eprintln!(
+            "warning: stopped at turn {}, the state of turn {} repeats every {} turns",
+            simulation.turn, first_turn, period
+        ),
+        RunOutcome::TurnLimitReached => {
+            eprintln!(
+                "warning: turn limit reached after {} turns",
+                simulation.turn
+            )
+        }
+    }
 
-        // Then
-        let expected_adventurer =
-            RawAdventurer::new("Lara".to_string(), 1, 0, Orientation::East, "A".to_string());
-        assert_eq!(expected_adventurer, result_adventurer)
+    if verbose {
+        draw(&simulation, fog);
     }
+    write_result(&simulation.to_hashmap(), &mut create_output(output)?)?;
 
-    #[test]
-    fn should_adventurer_get_treasure_when_move_to_treasure_position() {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            0,
-            0,
-            Orientation::South,
-            "A".to_string(),
-        );
+    if let Some(format) = option_value(args, "--report") {
+        let format: ReportFormat = format
+            .parse()
+            .unwrap_or_else(|error| panic!("Invalid value for --report : {}", error));
+        print!("{}", Report::new(&simulation).format(format));
+    }
 
-        // When
-        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
+    Ok(())
+}
 
-        // Then
-        let mut expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            0,
-            1,
-            Orientation::South,
-            "A".to_string(),
+/// Draws the map, then what the adventurer named `fog` sees of it, if any.
+fn draw(simulation: &Simulation, fog: Option<&str>) {
+    print!(
+        "{}",
+        render::render_with_legend(&simulation.map, &simulation.adventurers)
+    );
+
+    if let Some(name) = fog {
+        let index = simulation
+            .adventurers
+            .iter()
+            .position(|adventurer| adventurer.name == name)
+            .unwrap_or_else(|| panic!("Unknown adventurer for --fog : {}", name));
+        let observation = Observation::new(
+            &simulation.map,
+            &simulation.adventurers,
+            index,
+            simulation.sight,
+        );
+        print!(
+            "{}",
+            render::render_fog(&simulation.map, &simulation.adventurers, &observation)
         );
-        expected_adventurer = expected_adventurer.update_treasure();
-
-        assert_eq!(expected_adventurer, result_adventurer);
     }
+}
 
-    /* SHOULD ADVENTURER NOT MOVE */
-
-    #[test]
-    fn should_adventurer_do_nothing_when_moving_to_mountain_position() {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            1,
-            0,
-            Orientation::South,
-            "A".to_string(),
-        );
+fn plan(args: &[String]) -> io::Result<()> {
+    let input = positional_args(args)
+        .first()
+        .copied()
+        .unwrap_or(INPUT_FILE_PATH);
+    let defaults = PlannerOptions::default();
+    let options = PlannerOptions {
+        max_turns: parse_option(args, "--turns", defaults.max_turns),
+        beam_width: parse_option(args, "--beam", defaults.beam_width),
+        time_budget: Duration::from_millis(parse_option(
+            args,
+            "--budget-ms",
+            defaults.time_budget.as_millis() as u64,
+        )),
+    };
 
-        // When
-        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
+    let simulation =
+        Simulation::from_hashmap(&load_scenario(input)?).with_resolution(parse_resolution(args));
+    let plan = planner::plan(&simulation, &options);
 
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            1,
-            0,
-            Orientation::South,
-            "A".to_string(),
-        );
-
-        assert_eq!(expected_adventurer, result_adventurer);
+    for (adventurer, pattern) in simulation.adventurers.iter().zip(plan.patterns.iter()) {
+        println!("{} - {}", adventurer.name, pattern);
     }
+    println!("Score : {}", plan.score);
 
-    #[test]
-    fn should_adventurer_not_move_when_next_position_x_is_oustide_map() {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            2,
-            0,
-            Orientation::South,
-            "A".to_string(),
-        );
+    Ok(())
+}
 
-        // When
-        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 1, 0);
+fn check(args: &[String]) -> io::Result<()> {
+    let input = positional_args(args)
+        .first()
+        .copied()
+        .unwrap_or(INPUT_FILE_PATH);
+    let content = load_scenario(input)?;
+
+    let errors = validation::validate(&content);
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            println!("error: {}", error);
+        }
+        process::exit(1);
+    }
 
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            2,
-            0,
-            Orientation::South,
-            "A".to_string(),
+    let simulation = Simulation::from_hashmap(&content);
+    let starts: Vec<(usize, usize)> = simulation
+        .adventurers
+        .iter()
+        .map(|adventurer| adventurer.position)
+        .collect();
+    let report = simulation.map.analyse_reachability(&starts);
+
+    for position in report.unreachable_treasures.iter() {
+        println!(
+            "warning: treasure at {} - {} is unreachable",
+            position.0, position.1
         );
-
-        assert_eq!(expected_adventurer, result_adventurer);
     }
-
-    #[test]
-    fn should_adventurer_not_move_when_next_position_y_is_oustide_map() {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            0,
-            3,
-            Orientation::South,
-            "A".to_string(),
+    for position in report.unreachable_plains.iter() {
+        println!(
+            "warning: plain at {} - {} is unreachable",
+            position.0, position.1
         );
-
-        // When
-        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
-
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            0,
-            3,
-            Orientation::South,
-            "A".to_string(),
+    }
+    if report.components.len() > 1 {
+        println!(
+            "warning: passable grid is split into {} regions of sizes {:?}",
+            report.components.len(),
+            report
+                .components
+                .iter()
+                .map(|component| component.len())
+                .collect::<Vec<_>>()
         );
-
-        assert_eq!(expected_adventurer, result_adventurer);
     }
 
-    /* SHOULD GET SEQUENCE */
-
-    #[test]
-    fn should_get_adeventurer_action_sequence() {
-        // Given
-        let sequence = "DADG";
-
-        // When
-        let result = get_sequence(sequence);
-
-        // Then
-        let expected = ('D', vec!['A', 'D', 'G']);
+    Ok(())
+}
 
-        assert_eq!(expected, result);
+fn lint(args: &[String]) -> io::Result<()> {
+    let input = positional_args(args)
+        .first()
+        .copied()
+        .unwrap_or(INPUT_FILE_PATH);
+    let simulation = Simulation::from_hashmap(&load_scenario(input)?);
+
+    for (adventurer, suggestions) in simulation
+        .adventurers
+        .iter()
+        .zip(lint::lint_simulation(&simulation))
+        .map(|(adventurer, (_, suggestions))| (adventurer, suggestions))
+    {
+        for suggestion in suggestions.iter() {
+            let instructions: String = adventurer
+                .pattern
+                .chars()
+                .skip(suggestion.offset)
+                .take(suggestion.length)
+                .collect();
+            println!(
+                "{} - offset {} - {} : {}",
+                adventurer.name, suggestion.offset, instructions, suggestion.kind
+            );
+        }
     }
 
-    #[test]
-    #[should_panic(expected = "Input pattern is empty")]
-    fn should_panic_when_empty_action_sequence() {
-        // Given
-        let sequence = "";
+    Ok(())
+}
 
-        // When + Then
-        get_sequence(sequence);
+/// Generator options read from the options shared by `generate` and
+/// `tournament`.
+fn parse_generator_options(args: &[String]) -> GeneratorOptions {
+    let defaults = GeneratorOptions::default();
+
+    GeneratorOptions {
+        layout: parse_option(args, "--layout", defaults.layout),
+        width: parse_option(args, "--width", defaults.width),
+        height: parse_option(args, "--height", defaults.height),
+        mountain_density: parse_option(args, "--mountains", defaults.mountain_density),
+        treasure_piles: parse_option(args, "--treasures", defaults.treasure_piles),
+        pile_size: (
+            parse_option(args, "--pile-min", defaults.pile_size.0),
+            parse_option(args, "--pile-max", defaults.pile_size.1),
+        ),
+        adventurers: parse_option(args, "--adventurers", defaults.adventurers),
+        pattern_length: parse_option(args, "--pattern-length", defaults.pattern_length),
+        seed: parse_option(args, "--seed", defaults.seed),
     }
+}
 
-    /* SHOULD UPDATE ADVENTURER */
-
-    #[parameterized(
-        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East},
-        coordinates = {(0, 0), (1, 2), (0, 0), (0,0)},
-        expected_coordinates = {(0, 0), (1, 3), (0,0), (1,0)})]
-    fn should_adventurer_not_change_orientation_when_move(
-        orientation: Orientation,
-        coordinates: (usize, usize),
-        expected_coordinates: (usize, usize),
-    ) {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            coordinates.0,
-            coordinates.1,
-            orientation.clone(),
-            "AG".to_string(),
-        );
-
-        // When
-        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
+fn generate(args: &[String]) -> io::Result<()> {
+    let output = positional_args(args)
+        .first()
+        .copied()
+        .unwrap_or(GENERATED_FILE_PATH);
+    let options = parse_generator_options(args);
+
+    match generator::generate(&options) {
+        Ok(scenario) => write_scenario(&scenario, &mut create_output(output)?),
+        Err(error) => {
+            println!("error: {}", error);
+            process::exit(1);
+        }
+    }
+}
 
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            expected_coordinates.0,
-            expected_coordinates.1,
-            orientation.clone(),
-            "G".to_string(),
-        );
+fn batch(args: &[String]) -> io::Result<()> {
+    // Results are written next to the inputs, so they are never guessed
+    let Some(pattern) = positional_args(args).first().copied() else {
+        println!("error: batch expects a directory or a glob of scenarios");
+        process::exit(1);
+    };
+    let defaults = BatchOptions::default();
+    let options = BatchOptions {
+        jobs: parse_option(args, "--jobs", defaults.jobs),
+        output_dir: option_value(args, "--output-dir").map(Into::into),
+        resolution: parse_resolution(args),
+    };
 
-        assert_eq!(expected_adventurer, adventurer_result);
+    let (root, inputs) = batch::collect_inputs(pattern)?;
+    let outcomes = batch::run_batch(&root, &inputs, &options);
+
+    let mut failed = 0;
+    for outcome in outcomes.iter() {
+        match &outcome.result {
+            Ok(success) => println!(
+                "ok: {} -> {} : score {} in {} turns",
+                outcome.input.display(),
+                success.output.display(),
+                success.score,
+                success.turns
+            ),
+            Err(error) => {
+                failed += 1;
+                println!("error: {} : {}", outcome.input.display(), error);
+            }
+        }
+    }
+    println!(
+        "{} scenarios, {} succeeded, {} failed",
+        outcomes.len(),
+        outcomes.len() - failed,
+        failed
+    );
+
+    if failed > 0 {
+        process::exit(1);
     }
 
-    #[parameterized(
-        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East },
-        expected_orientation = { Orientation::West,  Orientation::East, Orientation::South, Orientation::North},
-        coordinates = {(0, 0), (0, 0), (0, 0), (0, 0)})]
-    fn should_adventurer_change_orientation_when_adventurer_turn_to_left(
-        orientation: Orientation,
-        expected_orientation: Orientation,
-        coordinates: (usize, usize),
-    ) {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            coordinates.0,
-            coordinates.1,
-            orientation,
-            "GA".to_string(),
-        );
-
-        // When
-        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
+    Ok(())
+}
 
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            coordinates.0,
-            coordinates.1,
-            expected_orientation,
-            "A".to_string(),
-        );
+fn tournament(args: &[String]) -> io::Result<()> {
+    let arenas = match positional_args(args).first() {
+        Some(pattern) => tournament::load_arenas(&batch::collect_inputs(pattern)?.1)?,
+        None => {
+            let count = parse_option(args, "--maps", 10);
+            match tournament::generate_arenas(&parse_generator_options(args), count) {
+                Ok(arenas) => arenas,
+                Err(error) => {
+                    println!("error: {}", error);
+                    process::exit(1);
+                }
+            }
+        }
+    };
+    let defaults = TournamentOptions::default();
+    let controllers = match option_value(args, "--controllers") {
+        Some(value) => value
+            .split(',')
+            .map(|kind| {
+                kind.parse()
+                    .unwrap_or_else(|error| panic!("Invalid value for --controllers : {}", error))
+            })
+            .collect(),
+        None => defaults.controllers,
+    };
+    let options = TournamentOptions {
+        controllers,
+        seeds: parse_option(args, "--seeds", defaults.seeds),
+        max_turns: parse_option(args, "--max-turns", defaults.max_turns),
+        resolution: parse_resolution(args),
+    };
 
-        assert_eq!(expected_adventurer, adventurer_result);
+    let tournament = Tournament::run(&arenas, &options);
+    if let Some(path) = option_value(args, "--scores") {
+        create_output(path)?.write_all(tournament.scores_csv(&options.controllers).as_bytes())?;
     }
+    print!(
+        "{}",
+        tournament.format(parse_option(args, "--format", TournamentFormat::Markdown))
+    );
 
-    #[parameterized(
-        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East },
-        expected_orientation = { Orientation::East,  Orientation::West, Orientation::North, Orientation::South},
-        coordinates = {(0, 0), (0, 0), (0, 0), (0, 0)})]
-    fn should_adventurer_change_orientation_when_adventurer_turn_to_right(
-        orientation: Orientation,
-        expected_orientation: Orientation,
-        coordinates: (usize, usize),
-    ) {
-        // Given
-        let mut mock_map = init_mock_map();
-        let fake_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            coordinates.0,
-            coordinates.1,
-            orientation,
-            "DA".to_string(),
-        );
-
-        // When
-        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
-
-        // Then
-        let expected_adventurer = RawAdventurer::new(
-            "Lara".to_string(),
-            coordinates.0,
-            coordinates.1,
-            expected_orientation,
-            "A".to_string(),
-        );
+    Ok(())
+}
 
-        assert_eq!(expected_adventurer, adventurer_result);
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..]),
+        Some("plan") => plan(&args[1..]),
+        Some("check") => check(&args[1..]),
+        Some("lint") => lint(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("batch") => batch(&args[1..]),
+        Some("tournament") => tournament(&args[1..]),
+        _ => run(&args),
     }
 }
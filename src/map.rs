@@ -1,52 +1,189 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::{
+    element::{Element, Empty, RawAdventurer, RawMap, RawTerrain, Terrain},
+    grid::{Geometry, Grid},
+    storage::{CellStore, Storage, StorageKind},
+};
+
+/// What happens at the edges of the map: `Toroidal` wraps both axes,
+/// `CylinderHorizontal` only wraps east-west (`x`) and `CylinderVertical`
+/// only north-south (`y`).
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Topology {
+    #[default]
+    Bounded,
+    Toroidal,
+    CylinderHorizontal,
+    CylinderVertical,
+}
+
+impl Topology {
+    pub fn wraps_x(&self) -> bool {
+        matches!(self, Topology::Toroidal | Topology::CylinderHorizontal)
+    }
+
+    pub fn wraps_y(&self) -> bool {
+        matches!(self, Topology::Toroidal | Topology::CylinderVertical)
+    }
 
-use crate::element::{Element, Empty, RawAdventurer, RawMap};
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Toroidal => "toroidal",
+            Topology::CylinderHorizontal => "cylinder-horizontal",
+            Topology::CylinderVertical => "cylinder-vertical",
+        }
+    }
+}
 
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        [
+            Topology::Bounded,
+            Topology::Toroidal,
+            Topology::CylinderHorizontal,
+            Topology::CylinderVertical,
+        ]
+        .into_iter()
+        .find(|topology| topology.name() == raw_value)
+        .ok_or_else(|| format!("Unknown topology : {}", raw_value))
+    }
+}
+
+#[derive(Clone)]
 pub struct Map {
     pub rows: usize,
     pub cols: usize,
-    pub elements: Vec<Vec<Element>>,
+    /// Elements and terrain of the cells, dense or sparse.
+    storage: Storage,
+    pub topology: Topology,
+    pub grid: Grid,
+    occupied: HashSet<(usize, usize)>,
+    /// Whether any cell is a bank, known once built since banks never change.
+    has_banks: bool,
 }
 
 impl Map {
+    /// Builds the map with the storage fitting the density of its content.
     pub fn new(rows: usize, cols: usize, content: HashMap<String, Vec<Element>>) -> Map {
-        let mut new_vec: Vec<Vec<Element>> = (0..rows)
-            .map(|row| {
-                (0..cols)
-                    .map(|col| Element::Empty(Empty::new(row, col)))
-                    .collect()
-            })
-            .collect();
+        let kind = StorageKind::for_content(rows, cols, &content);
 
-        Self::fill_with_data(&mut new_vec, &content);
+        Self::with_storage(rows, cols, content, kind)
+    }
 
-        Map {
+    pub fn with_storage(
+        rows: usize,
+        cols: usize,
+        content: HashMap<String, Vec<Element>>,
+        kind: StorageKind,
+    ) -> Map {
+        let topology = content
+            .get("Topology")
+            .into_iter()
+            .flatten()
+            .find_map(|element| match element {
+                Element::RawTopology(topology) => Some(*topology),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let grid = content
+            .get("Grid")
+            .into_iter()
+            .flatten()
+            .find_map(|element| match element {
+                Element::RawGrid(grid) => Some(*grid),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let mut the_map = Map {
             rows,
             cols,
-            elements: new_vec,
-        }
+            storage: Storage::new(rows, cols, kind),
+            topology,
+            grid,
+            occupied: HashSet::new(),
+            has_banks: false,
+        };
+        the_map.fill_with_data(&content);
+        let has_banks = the_map
+            .elements()
+            .any(|element| matches!(element, Element::RawBank(_)));
+        the_map.has_banks = has_banks;
+
+        the_map
+    }
+
+    fn check_bounds(&self, position: (usize, usize)) {
+        assert!(
+            position.0 < self.rows && position.1 < self.cols,
+            "index out of bounds: {:?} is outside the {}x{} map",
+            position,
+            self.rows,
+            self.cols
+        );
+    }
+
+    pub fn storage_kind(&self) -> StorageKind {
+        self.storage.kind()
+    }
+
+    /// Element of the cell, `None` when it is empty.
+    pub fn element_at(&self, position: (usize, usize)) -> Option<&Element> {
+        self.check_bounds(position);
+        self.storage.element(position)
+    }
+
+    /// Elements of the non-empty cells, `x` by `x`.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.storage.elements()
     }
 
     pub fn to_hashmap(&self, adventurers: Vec<RawAdventurer>) -> HashMap<String, Vec<Element>> {
         let mut hashmap: HashMap<String, Vec<Element>> = HashMap::new();
 
-        for (_, row) in self.elements.iter().enumerate() {
-            for (_, element) in row.iter().enumerate() {
-                let key = match element {
-                    Element::RawMountain(_) => "M".to_string(),
-                    Element::RawTreasure(_) => "T".to_string(),
-                    _ => continue,
-                };
+        for element in self.elements() {
+            let key = match element {
+                Element::RawMountain(_) => "M".to_string(),
+                Element::RawTreasure(_) => "T".to_string(),
+                Element::RawBank(_) => "B".to_string(),
+                // Each pair is kept once, from its smallest end
+                Element::RawPortal(portal) if portal.position < portal.destination => {
+                    "P".to_string()
+                }
+                _ => continue,
+            };
+            hashmap.entry(key).or_default().push(element.clone());
+        }
+
+        for ((x, y), terrain) in self.storage.terrains() {
+            if let Some(key) = terrain.key() {
                 hashmap
-                    .entry(key)
-                    .or_insert_with(Vec::new)
-                    .push(element.clone());
+                    .entry(key.to_string())
+                    .or_default()
+                    .push(Element::RawTerrain(RawTerrain::new(x, y, terrain)));
             }
         }
 
+        if self.grid != Grid::Square {
+            hashmap.insert("Grid".to_string(), vec![Element::RawGrid(self.grid)]);
+        }
+        if self.topology != Topology::Bounded {
+            hashmap.insert(
+                "Topology".to_string(),
+                vec![Element::RawTopology(self.topology)],
+            );
+        }
+
         hashmap
             .entry("C".to_string())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(Element::RawMap(RawMap::new(self.rows, self.cols)));
 
         hashmap.insert(
@@ -61,32 +198,130 @@ impl Map {
     }
 
     pub fn update_position(&mut self, x: usize, y: usize) {
-        if let Some(element) = self.elements.get_mut(x).and_then(|row| row.get_mut(y)) {
-            match element {
-                Element::RawTreasure(treasure_element) => {
-                    treasure_element.nb_treasure -= 1;
-                }
-                _ => (),
+        if x >= self.rows || y >= self.cols {
+            return;
+        }
+        if let Some(Element::RawTreasure(treasure_element)) = self.storage.element_mut((x, y)) {
+            treasure_element.nb_treasure -= 1;
+        }
+    }
+
+    /// Cell reached from `position` by moving of `(dx, dy)`, wrapping around
+    /// the edges the topology joins, if it is on the map.
+    pub fn target(&self, position: (usize, usize), dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let x = Self::shift(position.0, dx, self.rows, self.topology.wraps_x())?;
+        let y = Self::shift(position.1, dy, self.cols, self.topology.wraps_y())?;
+
+        Some((x, y))
+    }
+
+    fn shift(coordinate: usize, delta: isize, size: usize, wraps: bool) -> Option<usize> {
+        if wraps {
+            return Some((coordinate as isize + delta).rem_euclid(size as isize) as usize);
+        }
+
+        coordinate
+            .checked_add_signed(delta)
+            .filter(|value| *value < size)
+    }
+
+    pub fn geometry(&self) -> &'static dyn Geometry {
+        self.grid.geometry()
+    }
+
+    /// Number of steps between two cells, ignoring obstacles but taking the
+    /// way around the edges the topology joins.
+    pub fn distance(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        let axis = |from: usize, to: usize, size: usize, wraps: bool| {
+            let direct = to as isize - from as isize;
+            if wraps && direct.unsigned_abs() * 2 > size {
+                direct - direct.signum() * size as isize
+            } else {
+                direct
             }
+        };
+
+        self.geometry().distance(
+            axis(from.0, to.0, self.rows, self.topology.wraps_x()),
+            axis(from.1, to.1, self.cols, self.topology.wraps_y()),
+        )
+    }
+
+    pub fn has_banks(&self) -> bool {
+        self.has_banks
+    }
+
+    /// Treasure left in the piles of the map.
+    pub fn remaining_treasure(&self) -> usize {
+        self.elements()
+            .map(|element| match element {
+                Element::RawTreasure(treasure) => treasure.nb_treasure,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn portal_destination(&self, position: (usize, usize)) -> Option<(usize, usize)> {
+        match self.element_at(position) {
+            Some(Element::RawPortal(portal)) => Some(portal.destination),
+            _ => None,
         }
     }
 
-    pub fn print_map(map_data: &Vec<Vec<Element>>) {
-        for row in map_data {
-            for element in row {
-                print!("{:?} ", element);
+    pub fn terrain_at(&self, position: (usize, usize)) -> Terrain {
+        self.check_bounds(position);
+        self.storage.terrain(position)
+    }
+
+    pub fn is_occupied(&self, position: (usize, usize)) -> bool {
+        self.occupied.contains(&position)
+    }
+
+    pub fn occupy(&mut self, position: (usize, usize)) {
+        self.occupied.insert(position);
+    }
+
+    pub fn vacate(&mut self, position: (usize, usize)) {
+        self.occupied.remove(&position);
+    }
+
+    pub fn move_occupant(&mut self, from: (usize, usize), to: (usize, usize)) {
+        self.occupied.remove(&from);
+        self.occupied.insert(to);
+    }
+
+    pub fn print_map(&self) {
+        for x in 0..self.rows {
+            for y in 0..self.cols {
+                match self.element_at((x, y)) {
+                    Some(element) => print!("{:?} ", element),
+                    None => print!("{:?} ", Element::Empty(Empty::new(x, y))),
+                }
             }
             println!();
         }
     }
 
-    fn fill_with_data(my_map: &mut Vec<Vec<Element>>, content: &HashMap<String, Vec<Element>>) {
+    fn fill_with_data(&mut self, content: &HashMap<String, Vec<Element>>) {
         for (_, value) in content.iter() {
             for data in value.iter() {
                 match data {
-                    Element::RawMountain(_) | Element::RawTreasure(_) => {
-                        let coordinates = data.position().unwrap();
-                        my_map[coordinates.0][coordinates.1] = data.clone();
+                    Element::RawMountain(_) | Element::RawTreasure(_) | Element::RawBank(_) => {
+                        let position = data.position().unwrap();
+                        self.check_bounds(position);
+                        self.storage.set_element(position, data.clone());
+                    }
+                    Element::RawPortal(portal) => {
+                        self.check_bounds(portal.position);
+                        self.check_bounds(portal.destination);
+                        self.storage.set_element(portal.position, data.clone());
+                        self.storage
+                            .set_element(portal.destination, Element::RawPortal(portal.reversed()));
+                    }
+                    Element::RawTerrain(raw_terrain) => {
+                        self.check_bounds(raw_terrain.position);
+                        self.storage
+                            .set_terrain(raw_terrain.position, raw_terrain.terrain);
                     }
                     _ => (),
                 }
@@ -97,7 +332,9 @@ impl Map {
 
 #[cfg(test)]
 mod tests {
-    use crate::element::{Orientation, RawAdventurer, RawMap, RawMountain, RawTreasure};
+    use parameterized::parameterized;
+
+    use crate::element::{Orientation, RawAdventurer, RawMap, RawMountain, RawPortal, RawTreasure};
 
     use super::*;
 
@@ -136,18 +373,41 @@ mod tests {
         let my_map = init_mock_map();
 
         // Then
-        let mut expect: Vec<Vec<Element>> = (0..ROW_SIZE_MAP)
-            .map(|row| {
-                (0..COL_SIZE_MAP)
-                    .map(|col| Element::Empty(Empty::new(row, col)))
-                    .collect()
-            })
-            .collect();
-        expect[0][1] = Element::RawTreasure(RawTreasure::new(0, 1, 2));
-        expect[2][3] = Element::RawMountain(RawMountain::new(2, 3));
-        expect[1][1] = Element::RawMountain(RawMountain::new(1, 1));
+        let expect = vec![
+            Element::RawTreasure(RawTreasure::new(0, 1, 2)),
+            Element::RawMountain(RawMountain::new(1, 1)),
+            Element::RawMountain(RawMountain::new(2, 3)),
+        ];
+
+        assert_eq!(my_map.elements().cloned().collect::<Vec<_>>(), expect);
+        assert_eq!(
+            my_map.element_at((2, 3)),
+            Some(&Element::RawMountain(RawMountain::new(2, 3)))
+        );
+        assert_eq!(my_map.element_at((0, 0)), None);
+    }
+
+    #[parameterized(
+        size = { (ROW_SIZE_MAP, COL_SIZE_MAP), (1_000, 1_000) },
+        expected = { StorageKind::Dense, StorageKind::Sparse })]
+    fn should_store_large_empty_map_sparsely(size: (usize, usize), expected: StorageKind) {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 1, 2))],
+        );
+
+        // When
+        let my_map = Map::new(size.0, size.1, hash_map);
 
-        assert_eq!(my_map.elements, expect);
+        // Then
+        assert_eq!(my_map.storage_kind(), expected);
+        assert_eq!(
+            my_map.element_at((0, 1)),
+            Some(&Element::RawTreasure(RawTreasure::new(0, 1, 2)))
+        );
+        assert_eq!(my_map.terrain_at((size.0 - 1, size.1 - 1)), Terrain::Plain);
     }
 
     /* SHOULD UPDATE POSITION */
@@ -161,10 +421,129 @@ mod tests {
         Map::update_position(&mut my_map, 0, 1);
 
         // Then
-        let mut expect: Vec<Vec<Element>> = init_mock_map().elements;
-        expect[0][1] = Element::RawTreasure(RawTreasure::new(0, 1, 1));
+        assert_eq!(
+            my_map.element_at((0, 1)),
+            Some(&Element::RawTreasure(RawTreasure::new(0, 1, 1)))
+        );
+    }
+
+    /* SHOULD WRAP AROUND EDGES */
+
+    type Cell = Option<(usize, usize)>;
+
+    #[parameterized(
+        topology = { Topology::Bounded, Topology::Toroidal, Topology::CylinderHorizontal, Topology::CylinderVertical },
+        expected = { (None, None), (Some((2, 0)), Some((0, 3))), (Some((2, 0)), None), (None, Some((0, 3))) })]
+    fn should_target_across_edges_joined_by_topology(topology: Topology, expected: (Cell, Cell)) {
+        // Given
+        let mut my_map = init_mock_map();
+        my_map.topology = topology;
+
+        // When
+        let west = my_map.target((0, 0), -1, 0);
+        let north = my_map.target((0, 0), 0, -1);
+
+        // Then
+        assert_eq!((west, north), expected);
+    }
+
+    #[test]
+    fn should_measure_distance_around_the_torus() {
+        // Given
+        let mut my_map = init_mock_map();
+        my_map.topology = Topology::Toroidal;
+
+        // When + Then
+        assert_eq!(my_map.distance((0, 0), (2, 3)), 2);
+    }
+
+    #[test]
+    fn should_parse_topology_name() {
+        assert_eq!("cylinder-vertical".parse(), Ok(Topology::CylinderVertical));
+        assert!("sphere".parse::<Topology>().is_err());
+    }
+
+    /* SHOULD TRACK OCCUPIED CELLS */
+
+    #[test]
+    fn should_move_occupant_from_one_cell_to_another() {
+        // Given
+        let mut my_map = init_mock_map();
+        my_map.occupy((0, 0));
+
+        // When
+        my_map.move_occupant((0, 0), (1, 0));
+
+        // Then
+        assert!(!my_map.is_occupied((0, 0)));
+        assert!(my_map.is_occupied((1, 0)));
+    }
+
+    /* SHOULD LINK PORTALS */
+
+    #[test]
+    fn should_link_both_ends_of_portal() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(2, 3, 0, 0))],
+        );
 
-        assert_eq!(my_map.elements, expect);
+        // When
+        let my_map = Map::new(ROW_SIZE_MAP, COL_SIZE_MAP, hash_map);
+
+        // Then
+        assert_eq!(my_map.portal_destination((2, 3)), Some((0, 0)));
+        assert_eq!(my_map.portal_destination((0, 0)), Some((2, 3)));
+        assert_eq!(my_map.portal_destination((1, 0)), None);
+        assert_eq!(
+            my_map.to_hashmap(vec![])["P"],
+            vec![Element::RawPortal(RawPortal::new(0, 0, 2, 3))]
+        );
+    }
+
+    /* SHOULD BUILD TERRAIN LAYER */
+
+    #[test]
+    fn should_keep_terrain_under_elements() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 1, 2))],
+        );
+        hash_map.insert(
+            "I".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(0, 1, Terrain::Ice))],
+        );
+        hash_map.insert(
+            "V".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(
+                2,
+                0,
+                Terrain::Conveyor(Orientation::North),
+            ))],
+        );
+
+        // When
+        let my_map = Map::new(ROW_SIZE_MAP, COL_SIZE_MAP, hash_map.clone());
+
+        // Then
+        assert_eq!(my_map.terrain_at((0, 1)), Terrain::Ice);
+        assert_eq!(
+            my_map.terrain_at((2, 0)),
+            Terrain::Conveyor(Orientation::North)
+        );
+        assert_eq!(my_map.terrain_at((1, 0)), Terrain::Plain);
+        assert_eq!(
+            my_map.element_at((0, 1)),
+            Some(&Element::RawTreasure(RawTreasure::new(0, 1, 2)))
+        );
+        let result = my_map.to_hashmap(vec![]);
+        for key in ["T", "I", "V"] {
+            assert_eq!(result[key], hash_map[key]);
+        }
     }
 
     /* SHOULD CONVERT TO HASHMAP */
@@ -0,0 +1,347 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    controller::Instruction,
+    element::{Element, Orientation, RawAdventurer, Terrain},
+    grid::{Geometry, Grid},
+    map::Map,
+};
+
+/// How far an adventurer sees: the cells at most `radius` steps away and,
+/// with `line_of_sight`, only those that no mountain hides.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Sight {
+    pub radius: usize,
+    pub line_of_sight: bool,
+}
+
+impl Default for Sight {
+    fn default() -> Self {
+        Sight {
+            radius: 3,
+            line_of_sight: false,
+        }
+    }
+}
+
+/// What lies on a seen cell, besides its terrain and adventurer.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum CellContent {
+    Nothing,
+    Mountain,
+    Treasure(usize),
+    Bank,
+    Portal,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SeenAdventurer {
+    pub name: String,
+    pub orientation: Orientation,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CellView {
+    pub terrain: Terrain,
+    pub content: CellContent,
+    pub adventurer: Option<SeenAdventurer>,
+}
+
+impl CellView {
+    pub fn is_passable(&self) -> bool {
+        self.content != CellContent::Mountain && self.terrain != Terrain::Water
+    }
+
+    /// Whether an adventurer could enter the cell now.
+    pub fn is_free(&self) -> bool {
+        self.is_passable() && self.adventurer.is_none()
+    }
+
+    pub fn treasure(&self) -> usize {
+        match self.content {
+            CellContent::Treasure(treasure) => treasure,
+            _ => 0,
+        }
+    }
+}
+
+/// What an adventurer sees of the map around it, and all that behaviours
+/// choosing its instructions get to know of it. Cells are found by their
+/// offset from the adventurer; on a map whose edges are joined, several
+/// offsets may lead to the same cell.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Observation {
+    pub position: (usize, usize),
+    pub orientation: Orientation,
+    pub grid: Grid,
+    pub sight: Sight,
+    /// Next instruction of the pattern of the adventurer, if any is left.
+    pub next_instruction: Option<Instruction>,
+    /// Last instruction commanded to the adventurer from outside the
+    /// simulation, if any.
+    pub command: Option<Instruction>,
+    offsets: BTreeMap<(isize, isize), (usize, usize)>,
+    cells: BTreeMap<(usize, usize), CellView>,
+}
+
+impl Observation {
+    /// Observation of the adventurer at `index` among `adventurers`, which
+    /// are all on `the_map`.
+    pub fn new(
+        the_map: &Map,
+        adventurers: &[RawAdventurer],
+        index: usize,
+        sight: Sight,
+    ) -> Observation {
+        let adventurer = &adventurers[index];
+        let geometry = the_map.geometry();
+        let radius = sight.radius as isize;
+
+        let mut in_range: Vec<(isize, isize)> = (-radius..=radius)
+            .flat_map(|dx| (-radius..=radius).map(move |dy| (dx, dy)))
+            .filter(|(dx, dy)| geometry.distance(*dx, *dy) <= sight.radius)
+            .collect();
+        in_range.sort_by_key(|(dx, dy)| geometry.distance(*dx, *dy));
+
+        let mut observation = Observation {
+            position: adventurer.position,
+            orientation: adventurer.orientation,
+            grid: the_map.grid,
+            sight,
+            next_instruction: next_instruction(adventurer),
+            command: None,
+            offsets: BTreeMap::new(),
+            cells: BTreeMap::new(),
+        };
+        for (dx, dy) in in_range {
+            let Some(position) = the_map.target(adventurer.position, dx, dy) else {
+                continue;
+            };
+            if sight.line_of_sight && Self::is_hidden(the_map, adventurer.position, dx, dy) {
+                continue;
+            }
+
+            observation.offsets.insert((dx, dy), position);
+            observation
+                .cells
+                .entry(position)
+                .or_insert_with(|| Self::view(the_map, adventurers, position));
+        }
+
+        observation
+    }
+
+    /// Whether a mountain, or an edge of the map, stands between the cell
+    /// at `from` and the one at `(dx, dy)` from it.
+    fn is_hidden(the_map: &Map, from: (usize, usize), dx: isize, dy: isize) -> bool {
+        let line = the_map.geometry().line(dx, dy);
+
+        line.iter()
+            .skip(1)
+            .take(line.len().saturating_sub(2))
+            .any(|(x, y)| match the_map.target(from, *x, *y) {
+                Some(position) => {
+                    matches!(the_map.element_at(position), Some(Element::RawMountain(_)))
+                }
+                None => true,
+            })
+    }
+
+    fn view(the_map: &Map, adventurers: &[RawAdventurer], position: (usize, usize)) -> CellView {
+        let content = match the_map.element_at(position) {
+            Some(Element::RawMountain(_)) => CellContent::Mountain,
+            Some(Element::RawTreasure(treasure)) if treasure.nb_treasure > 0 => {
+                CellContent::Treasure(treasure.nb_treasure)
+            }
+            Some(Element::RawBank(_)) => CellContent::Bank,
+            Some(Element::RawPortal(_)) => CellContent::Portal,
+            _ => CellContent::Nothing,
+        };
+        let adventurer = if the_map.is_occupied(position) {
+            adventurers
+                .iter()
+                .find(|adventurer| adventurer.position == position)
+                .map(|adventurer| SeenAdventurer {
+                    name: adventurer.name.clone(),
+                    orientation: adventurer.orientation,
+                })
+        } else {
+            None
+        };
+
+        CellView {
+            terrain: the_map.terrain_at(position),
+            content,
+            adventurer,
+        }
+    }
+
+    pub fn geometry(&self) -> &'static dyn Geometry {
+        self.grid.geometry()
+    }
+
+    /// Observation of an adventurer seeing no cell at all, not even its own.
+    pub fn blind(adventurer: &RawAdventurer, grid: Grid) -> Observation {
+        Observation {
+            position: adventurer.position,
+            orientation: adventurer.orientation,
+            grid,
+            sight: Sight {
+                radius: 0,
+                line_of_sight: false,
+            },
+            next_instruction: next_instruction(adventurer),
+            command: None,
+            offsets: BTreeMap::new(),
+            cells: BTreeMap::new(),
+        }
+    }
+
+    /// Cell of the adventurer, unless it is blind.
+    pub fn here(&self) -> Option<&CellView> {
+        self.cells.get(&self.position)
+    }
+
+    /// Cell at `(dx, dy)` from the adventurer, if it is seen.
+    pub fn at(&self, dx: isize, dy: isize) -> Option<&CellView> {
+        self.cells.get(self.offsets.get(&(dx, dy))?)
+    }
+
+    /// Cell next to the adventurer towards `orientation`, if it is seen.
+    pub fn towards(&self, orientation: Orientation) -> Option<&CellView> {
+        let (dx, dy) = self.geometry().offset(orientation)?;
+
+        self.at(dx, dy)
+    }
+
+    pub fn is_visible(&self, position: (usize, usize)) -> bool {
+        self.cells.contains_key(&position)
+    }
+
+    /// Seen cells by offset from the adventurer, nearest first.
+    pub fn cells(&self) -> impl Iterator<Item = ((isize, isize), &CellView)> {
+        let mut offsets: Vec<&(isize, isize)> = self.offsets.keys().collect();
+        offsets.sort_by_key(|(dx, dy)| self.geometry().distance(*dx, *dy));
+
+        offsets
+            .into_iter()
+            .map(|offset| (*offset, &self.cells[&self.offsets[offset]]))
+    }
+}
+
+fn next_instruction(adventurer: &RawAdventurer) -> Option<Instruction> {
+    adventurer.next_instruction().map(Instruction::from_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::{
+        element::{RawMountain, RawTreasure},
+        map::Topology,
+    };
+
+    /* MOCK INIT */
+
+    /// Corridor of 7 x 1 with Lara at 1 facing east, a mountain at 3, a
+    /// treasure at 5 and Toto at 0.
+    fn init_mock_corridor() -> (Map, Vec<RawAdventurer>) {
+        let mut content = HashMap::new();
+        content.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(3, 0))],
+        );
+        content.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(5, 0, 2))],
+        );
+        let mut the_map = Map::new(7, 1, content);
+        let adventurers = vec![
+            RawAdventurer::new("Lara".to_string(), 1, 0, Orientation::East, String::new()),
+            RawAdventurer::new("Toto".to_string(), 0, 0, Orientation::North, String::new()),
+        ];
+        for adventurer in adventurers.iter() {
+            the_map.occupy(adventurer.position);
+        }
+
+        (the_map, adventurers)
+    }
+
+    fn visible_xs(observation: &Observation) -> Vec<usize> {
+        (0..7).filter(|x| observation.is_visible((*x, 0))).collect()
+    }
+
+    /* SHOULD SEE */
+
+    #[parameterized(
+        radius = { 0, 2, 4, 4 },
+        line_of_sight = { false, false, false, true },
+        expected = { vec![1], vec![0, 1, 2, 3], vec![0, 1, 2, 3, 4, 5], vec![0, 1, 2, 3] })]
+    fn should_see_cells_within_radius_unless_hidden(
+        radius: usize,
+        line_of_sight: bool,
+        expected: Vec<usize>,
+    ) {
+        // Given
+        let (the_map, adventurers) = init_mock_corridor();
+        let sight = Sight {
+            radius,
+            line_of_sight,
+        };
+
+        // When
+        let observation = Observation::new(&the_map, &adventurers, 0, sight);
+
+        // Then
+        assert_eq!(visible_xs(&observation), expected);
+    }
+
+    #[test]
+    fn should_expose_terrain_treasure_and_adventurers_of_seen_cells() {
+        // Given
+        let (the_map, adventurers) = init_mock_corridor();
+
+        let sight = Sight {
+            radius: 4,
+            line_of_sight: false,
+        };
+
+        // When
+        let observation = Observation::new(&the_map, &adventurers, 0, sight);
+
+        // Then
+        assert_eq!(observation.at(4, 0).unwrap().treasure(), 2);
+        assert_eq!(
+            observation.towards(Orientation::West).unwrap().adventurer,
+            Some(SeenAdventurer {
+                name: "Toto".to_string(),
+                orientation: Orientation::North
+            })
+        );
+        assert_eq!(
+            observation.towards(Orientation::East).unwrap().content,
+            CellContent::Nothing
+        );
+        assert_eq!(observation.at(2, 0).unwrap().content, CellContent::Mountain);
+        assert!(observation.towards(Orientation::North).is_none());
+        assert!(!observation.here().unwrap().is_free());
+    }
+
+    #[test]
+    fn should_see_around_joined_edges() {
+        // Given
+        let (mut the_map, adventurers) = init_mock_corridor();
+        the_map.topology = Topology::Toroidal;
+
+        // When
+        let observation = Observation::new(&the_map, &adventurers, 0, Sight::default());
+
+        // Then
+        assert_eq!(visible_xs(&observation), vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(observation.towards(Orientation::North), observation.here());
+    }
+}
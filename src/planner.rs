@@ -0,0 +1,465 @@
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    element::{Element, Orientation},
+    simulation::Simulation,
+};
+
+const INSTRUCTIONS: [char; 3] = ['A', 'G', 'D'];
+
+pub struct PlannerOptions {
+    pub max_turns: usize,
+    pub beam_width: usize,
+    pub time_budget: Duration,
+}
+
+impl Default for PlannerOptions {
+    fn default() -> Self {
+        Self {
+            max_turns: 20,
+            beam_width: 64,
+            time_budget: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Plan {
+    pub patterns: Vec<String>,
+    pub score: usize,
+}
+
+#[derive(Clone)]
+struct Node {
+    /// Simulation at the start of the turn being planned.
+    start: Rc<Simulation>,
+    /// Instructions tried for each adventurer on that turn.
+    turn: Vec<String>,
+    /// Simulation once the turn is played with these instructions.
+    simulation: Simulation,
+    patterns: Vec<String>,
+}
+
+impl Node {
+    fn new(simulation: Simulation) -> Node {
+        let adventurers = simulation.adventurers.len();
+
+        Node {
+            start: Rc::new(simulation.clone()),
+            turn: vec![String::new(); adventurers],
+            simulation,
+            patterns: vec![String::new(); adventurers],
+        }
+    }
+
+    /// Starts planning the next turn, where no adventurer plays anything yet.
+    fn next_turn(&mut self) {
+        self.start = Rc::new(self.simulation.clone());
+        self.turn.iter_mut().for_each(String::clear);
+        self.simulation = play_turn(&self.start, &self.turn);
+    }
+
+    /// Same node where the adventurer at `index` tries `instructions` on the
+    /// turn being planned.
+    fn with_instructions(&self, index: usize, instructions: String) -> Node {
+        let mut turn = self.turn.clone();
+        turn[index] = instructions;
+
+        Node {
+            start: Rc::clone(&self.start),
+            simulation: play_turn(&self.start, &turn),
+            turn,
+            patterns: self.patterns.clone(),
+        }
+    }
+
+    /// Adds to the patterns the instructions played on the turn, leaving out
+    /// those a swamp kept the adventurer from playing.
+    fn end_turn(&mut self) {
+        for ((pattern, instructions), adventurer) in self
+            .patterns
+            .iter_mut()
+            .zip(self.turn.iter())
+            .zip(self.simulation.adventurers.iter())
+        {
+            pattern.push_str(&instructions[..adventurer.cursor]);
+        }
+    }
+}
+
+type StateKey = (Vec<((usize, usize), Orientation, usize)>, Vec<usize>);
+
+/// Searches patterns for every adventurer of the simulation so that the team
+/// collects as much treasure as possible.
+///
+/// The search is a beam search over turns, each played by the simulation
+/// itself so that speeds, swamps and the resolution mode act as they will
+/// when the patterns are replayed. Within a turn adventurers are expanded one
+/// after the other in declaration order, each trying as many instructions as
+/// it can play on the turn, and the beam is pruned on the turn played with
+/// the instructions chosen so far. The time budget is checked between turns
+/// and the best plan found so far is returned when it runs out, with the
+/// score the search reached, which replaying its patterns on the simulation
+/// gives too.
+pub fn plan(simulation: &Simulation, options: &PlannerOptions) -> Plan {
+    let started = Instant::now();
+    let treasure_cells = treasure_cells(simulation);
+
+    let mut start = simulation.clone();
+    for index in 0..start.adventurers.len() {
+        start.set_pattern(index, String::new());
+    }
+
+    let mut beam = vec![Node::new(start)];
+    let mut best = beam[0].clone();
+
+    for _ in 0..options.max_turns {
+        if started.elapsed() >= options.time_budget {
+            break;
+        }
+
+        for node in beam.iter_mut() {
+            node.next_turn();
+        }
+        for index in 0..simulation.adventurers.len() {
+            let candidates = beam
+                .iter()
+                .flat_map(|node| {
+                    // A waiting adventurer needs instructions to act, even
+                    // though it plays none of them
+                    let actions = node.start.adventurers[index]
+                        .speed
+                        .actions_on_turn(node.start.turn);
+                    sequences(actions)
+                        .into_iter()
+                        .map(move |instructions| node.with_instructions(index, instructions))
+                })
+                .collect();
+            beam = prune(candidates, &treasure_cells, options.beam_width);
+        }
+        for node in beam.iter_mut() {
+            node.end_turn();
+        }
+
+        if beam[0].simulation.score() > best.simulation.score() {
+            best = beam[0].clone();
+        }
+    }
+
+    Plan {
+        score: best.simulation.score(),
+        patterns: best.patterns,
+    }
+}
+
+/// Runs the simulation with the given patterns and returns the team score.
+pub fn replay(simulation: &Simulation, patterns: &[String]) -> usize {
+    let mut replayed = simulation.clone();
+    for (index, pattern) in patterns.iter().enumerate() {
+        replayed.set_pattern(index, pattern.clone());
+    }
+
+    replayed.run();
+    replayed.score()
+}
+
+/// Plays one turn from `start` where each adventurer has `instructions` to
+/// play, as replaying the patterns would.
+fn play_turn(start: &Simulation, instructions: &[String]) -> Simulation {
+    let mut played = start.clone();
+    for (index, instructions) in instructions.iter().enumerate() {
+        played.set_pattern(index, instructions.clone());
+    }
+    // The turn passes even when no one has an instruction to play on it
+    if !played.step() {
+        played.turn += 1;
+    }
+
+    played
+}
+
+/// Every sequence of `length` instructions.
+fn sequences(length: usize) -> Vec<String> {
+    (0..length).fold(vec![String::new()], |sequences, _| {
+        sequences
+            .iter()
+            .flat_map(|sequence| {
+                INSTRUCTIONS
+                    .iter()
+                    .map(move |instruction| format!("{}{}", sequence, instruction))
+            })
+            .collect()
+    })
+}
+
+fn prune(candidates: Vec<Node>, treasure_cells: &[(usize, usize)], beam_width: usize) -> Vec<Node> {
+    let mut scored: Vec<(usize, usize, Node)> = candidates
+        .into_iter()
+        .map(|node| {
+            let distance = distance_to_treasures(&node.simulation, treasure_cells);
+            (node.simulation.score(), distance, node)
+        })
+        .collect();
+    scored.sort_by_key(|(score, distance, _)| (Reverse(*score), *distance));
+
+    let mut seen: HashSet<StateKey> = HashSet::new();
+    scored
+        .into_iter()
+        .map(|(_, _, node)| node)
+        .filter(|node| seen.insert(state_key(&node.simulation, treasure_cells)))
+        .take(beam_width)
+        .collect()
+}
+
+fn treasure_cells(simulation: &Simulation) -> Vec<(usize, usize)> {
+    simulation
+        .map
+        .elements()
+        .filter_map(|element| match element {
+            Element::RawTreasure(treasure) => Some(treasure.position),
+            _ => None,
+        })
+        .collect()
+}
+
+fn remaining_treasure(simulation: &Simulation, position: (usize, usize)) -> usize {
+    match simulation.map.element_at(position) {
+        Some(Element::RawTreasure(treasure)) => treasure.nb_treasure,
+        _ => 0,
+    }
+}
+
+fn distance_to_treasures(simulation: &Simulation, treasure_cells: &[(usize, usize)]) -> usize {
+    simulation
+        .adventurers
+        .iter()
+        .map(|adventurer| {
+            treasure_cells
+                .iter()
+                .filter(|position| remaining_treasure(simulation, **position) > 0)
+                .map(|position| simulation.map.distance(adventurer.position, *position))
+                .min()
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+fn state_key(simulation: &Simulation, treasure_cells: &[(usize, usize)]) -> StateKey {
+    (
+        simulation
+            .adventurers
+            .iter()
+            .map(|adventurer| {
+                (
+                    adventurer.position,
+                    adventurer.orientation,
+                    adventurer.treasure,
+                )
+            })
+            .collect(),
+        treasure_cells
+            .iter()
+            .map(|position| remaining_treasure(simulation, *position))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::{
+        element::{RawAdventurer, RawMap, RawMountain, RawTerrain, RawTreasure, Speed, Terrain},
+        simulation::{ConflictPolicy, ResolutionMode},
+    };
+
+    /* MOCK INIT */
+
+    fn init_mock_simulation(adventurers: Vec<RawAdventurer>) -> Simulation {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("C".to_string(), vec![Element::RawMap(RawMap::new(4, 4))]);
+        hash_map.insert(
+            "M".to_string(),
+            vec![
+                Element::RawMountain(RawMountain::new(0, 1)),
+                Element::RawMountain(RawMountain::new(1, 1)),
+            ],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![
+                Element::RawTreasure(RawTreasure::new(1, 2, 2)),
+                Element::RawTreasure(RawTreasure::new(3, 3, 1)),
+            ],
+        );
+        hash_map.insert(
+            "A".to_string(),
+            adventurers
+                .into_iter()
+                .map(Element::RawAdventurer)
+                .collect(),
+        );
+
+        Simulation::from_hashmap(&hash_map)
+    }
+
+    /* SHOULD PLAN */
+
+    #[test]
+    fn should_plan_patterns_collecting_every_treasure_for_one_adventurer() {
+        // Given
+        let simulation = init_mock_simulation(vec![RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            "".to_string(),
+        )]);
+
+        // When
+        let plan = plan(&simulation, &PlannerOptions::default());
+
+        // Then
+        assert_eq!(plan.score, 3);
+        assert_eq!(replay(&simulation, &plan.patterns), plan.score);
+    }
+
+    #[test]
+    fn should_plan_patterns_for_every_adventurer_in_declaration_order() {
+        // Given
+        let simulation = init_mock_simulation(vec![
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::South, "".to_string()),
+            RawAdventurer::new("Toto".to_string(), 3, 0, Orientation::South, "".to_string()),
+        ]);
+
+        // When
+        let plan = plan(&simulation, &PlannerOptions::default());
+
+        // Then
+        assert_eq!(plan.patterns.len(), 2);
+        assert_eq!(plan.score, 3);
+        assert_eq!(replay(&simulation, &plan.patterns), plan.score);
+    }
+
+    #[parameterized(
+        speed = { Speed::new(2, 1), Speed::new(1, 2) },
+        expected = { 3, 1 })]
+    fn should_plan_as_many_instructions_as_speed_allows(speed: Speed, expected: usize) {
+        // Given
+        let mut lara =
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::South, "".to_string());
+        lara.speed = speed;
+        let simulation = init_mock_simulation(vec![lara]);
+        let options = PlannerOptions {
+            max_turns: 16,
+            ..PlannerOptions::default()
+        };
+
+        // When
+        let plan = plan(&simulation, &options);
+
+        // Then
+        assert_eq!(plan.score, expected);
+        assert_eq!(replay(&simulation, &plan.patterns), plan.score);
+    }
+
+    #[parameterized(policy = {
+        ConflictPolicy::BothBlocked, ConflictPolicy::PriorityByName, ConflictPolicy::Random(3)
+    })]
+    fn should_plan_patterns_in_simultaneous_mode(policy: ConflictPolicy) {
+        // Given
+        let simulation = init_mock_simulation(vec![
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::South, "".to_string()),
+            RawAdventurer::new("Toto".to_string(), 3, 0, Orientation::South, "".to_string()),
+        ])
+        .with_resolution(ResolutionMode::Simultaneous(policy));
+
+        // When
+        let plan = plan(&simulation, &PlannerOptions::default());
+
+        // Then
+        assert_eq!(plan.score, 3);
+        assert_eq!(replay(&simulation, &plan.patterns), plan.score);
+    }
+
+    #[test]
+    fn should_not_plan_instructions_while_waiting_in_swamp() {
+        // Given
+        // L % .
+        // M . .
+        // . T .
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("C".to_string(), vec![Element::RawMap(RawMap::new(3, 3))]);
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(0, 1))],
+        );
+        hash_map.insert(
+            "S".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(1, 0, Terrain::Swamp))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(1, 2, 1))],
+        );
+        hash_map.insert(
+            "A".to_string(),
+            vec![Element::RawAdventurer(RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                "".to_string(),
+            ))],
+        );
+        let simulation = Simulation::from_hashmap(&hash_map);
+        let options = PlannerOptions {
+            max_turns: 5,
+            ..PlannerOptions::default()
+        };
+
+        // When
+        let plan = plan(&simulation, &options);
+
+        // Then
+        assert_eq!(plan.patterns, vec!["ADAA".to_string()]);
+        assert_eq!(plan.score, 1);
+        assert_eq!(replay(&simulation, &plan.patterns), plan.score);
+    }
+
+    #[test]
+    fn should_return_empty_plan_when_no_time_budget() {
+        // Given
+        let simulation = init_mock_simulation(vec![RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            "".to_string(),
+        )]);
+        let options = PlannerOptions {
+            time_budget: Duration::ZERO,
+            ..PlannerOptions::default()
+        };
+
+        // When
+        let plan = plan(&simulation, &options);
+
+        // Then
+        assert_eq!(
+            plan,
+            Plan {
+                patterns: vec!["".to_string()],
+                score: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    element::{Element, Terrain},
+    map::Map,
+};
+
+#[derive(PartialEq, Debug)]
+pub struct ReachabilityReport {
+    pub unreachable_treasures: Vec<(usize, usize)>,
+    pub unreachable_plains: Vec<(usize, usize)>,
+    pub components: Vec<Vec<(usize, usize)>>,
+}
+
+impl Map {
+    pub fn is_passable(&self, position: (usize, usize)) -> bool {
+        !matches!(self.element_at(position), Some(Element::RawMountain(_)))
+            && self.terrain_at(position) != Terrain::Water
+    }
+
+    /// Cell entered by moving `(dx, dy)` from `position`, unless it is off
+    /// the map, impassable or a conveyor entered against its orientation.
+    /// Other adventurers are not checked.
+    pub fn entry(&self, position: (usize, usize), dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let target = self.target(position, dx, dy)?;
+        if !self.is_passable(target) {
+            return None;
+        }
+        if let Terrain::Conveyor(orientation) = self.terrain_at(target) {
+            if self.geometry().offset(orientation) == Some((-dx, -dy)) {
+                return None;
+            }
+        }
+
+        Some(target)
+    }
+
+    /// Cells entered by an adventurer on `position` advancing by `(dx, dy)`
+    /// when no one is in the way, the last one being where it stops: as in
+    /// the game, it takes the portals it enters, slides over ice and rides
+    /// conveyors.
+    pub fn path(&self, position: (usize, usize), dx: isize, dy: isize) -> Vec<(usize, usize)> {
+        let mut path = Vec::new();
+        let (mut current, mut dx, mut dy) = (position, dx, dy);
+
+        // Bounds slides and conveyor chains, which could loop forever
+        for _ in 0..self.rows * self.cols {
+            if dx == 0 && dy == 0 {
+                break;
+            }
+            let Some(target) = self.entry(current, dx, dy) else {
+                break;
+            };
+            path.push(target);
+            current = match self.portal_destination(target) {
+                Some(destination) if self.is_passable(destination) => {
+                    path.push(destination);
+                    destination
+                }
+                _ => target,
+            };
+
+            match self.terrain_at(current) {
+                Terrain::Ice => (),
+                Terrain::Conveyor(orientation) => {
+                    (dx, dy) = self.geometry().offset(orientation).unwrap_or((0, 0))
+                }
+                _ => break,
+            }
+        }
+
+        path
+    }
+
+    /// Paths of the advances from `position` in every direction.
+    fn paths(&self, position: (usize, usize)) -> impl Iterator<Item = Vec<(usize, usize)>> + '_ {
+        let geometry = self.geometry();
+        geometry.orientations().iter().map(move |orientation| {
+            let (dx, dy) = geometry.offset(*orientation).unwrap_or((0, 0));
+            self.path(position, dx, dy)
+        })
+    }
+
+    /// Cells where an adventurer on `position` stops after one advance,
+    /// across the edges the topology joins, through portals and at the end
+    /// of slides and conveyor rides.
+    pub fn neighbours(&self, position: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::new();
+
+        for path in self.paths(position) {
+            if let Some(&stop) = path.last() {
+                if stop != position && !neighbours.contains(&stop) {
+                    neighbours.push(stop);
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    /// Cells an adventurer on `start` can enter by turning on the spot and
+    /// advancing. It only advances again from where its moves stop, but
+    /// enters every cell it slides or rides over. Other adventurers are
+    /// ignored since they only block temporarily.
+    pub fn reachable_from(&self, start: (usize, usize)) -> HashSet<(usize, usize)> {
+        let mut reached = HashSet::new();
+        if start.0 >= self.rows || start.1 >= self.cols || !self.is_passable(start) {
+            return reached;
+        }
+
+        let mut stops = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        reached.insert(start);
+
+        while let Some(position) = queue.pop_front() {
+            for path in self.paths(position) {
+                reached.extend(path.iter().copied());
+                if let Some(&stop) = path.last() {
+                    if stops.insert(stop) {
+                        queue.push_back(stop);
+                    }
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// Splits the passable grid into regions, each sorted, in the order of
+    /// their first cell: a region holds the cells reachable from its first
+    /// cell that no earlier region holds, since conveyors only go one way.
+    pub fn connected_components(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut visited: HashSet<(usize, usize)> = HashSet::new();
+        let mut components = Vec::new();
+
+        for x in 0..self.rows {
+            for y in 0..self.cols {
+                if visited.contains(&(x, y)) || !self.is_passable((x, y)) {
+                    continue;
+                }
+
+                let mut component: Vec<(usize, usize)> = self
+                    .reachable_from((x, y))
+                    .into_iter()
+                    .filter(|position| !visited.contains(position))
+                    .collect();
+                component.sort();
+                visited.extend(component.iter().cloned());
+                components.push(component);
+            }
+        }
+
+        components
+    }
+
+    pub fn analyse_reachability(&self, starts: &[(usize, usize)]) -> ReachabilityReport {
+        let reached: HashSet<(usize, usize)> = starts
+            .iter()
+            .flat_map(|start| self.reachable_from(*start))
+            .collect();
+
+        let mut unreachable_treasures = Vec::new();
+        let mut unreachable_plains = Vec::new();
+
+        for position in (0..self.rows).flat_map(|x| (0..self.cols).map(move |y| (x, y))) {
+            if reached.contains(&position) {
+                continue;
+            }
+
+            match self.element_at(position) {
+                Some(Element::RawTreasure(_)) => unreachable_treasures.push(position),
+                None if self.terrain_at(position) == Terrain::Plain => {
+                    unreachable_plains.push(position)
+                }
+                _ => (),
+            }
+        }
+
+        ReachabilityReport {
+            unreachable_treasures,
+            unreachable_plains,
+            components: self.connected_components(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use crate::element::{Orientation, RawMountain, RawPortal, RawTerrain, RawTreasure};
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    // . M .
+    // M M .
+    // T . .
+    fn init_mock_map() -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![
+                Element::RawMountain(RawMountain::new(1, 0)),
+                Element::RawMountain(RawMountain::new(0, 1)),
+                Element::RawMountain(RawMountain::new(1, 1)),
+            ],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(2, 0, 1))],
+        );
+
+        Map::new(3, 3, hash_map)
+    }
+
+    /* SHOULD FLOOD FILL */
+
+    #[test]
+    fn should_reach_only_start_when_enclosed_by_mountains() {
+        // Given
+        let my_map = init_mock_map();
+
+        // When
+        let reached = my_map.reachable_from((0, 0));
+
+        // Then
+        assert_eq!(reached, HashSet::from([(0, 0)]));
+    }
+
+    #[test]
+    fn should_reach_nothing_when_starting_on_mountain() {
+        // Given
+        let my_map = init_mock_map();
+
+        // When
+        let reached = my_map.reachable_from((1, 1));
+
+        // Then
+        assert!(reached.is_empty());
+    }
+
+    #[test]
+    fn should_reach_through_portal() {
+        // Given
+        // . P .
+        // M M M
+        // . P .
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            (0..3)
+                .map(|x| Element::RawMountain(RawMountain::new(x, 1)))
+                .collect(),
+        );
+        hash_map.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(1, 0, 1, 2))],
+        );
+        let my_map = Map::new(3, 3, hash_map);
+
+        // When
+        let reached = my_map.reachable_from((0, 0));
+
+        // Then
+        assert_eq!(reached.len(), 6);
+        assert_eq!(my_map.neighbours((0, 0)), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn should_not_take_portal_without_entering_it() {
+        // Given: a portal leading out of a cell enclosed by mountains
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![
+                Element::RawMountain(RawMountain::new(1, 0)),
+                Element::RawMountain(RawMountain::new(0, 1)),
+            ],
+        );
+        hash_map.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(0, 0, 2, 2))],
+        );
+        let my_map = Map::new(3, 3, hash_map);
+
+        // When
+        let reached = my_map.reachable_from((0, 0));
+
+        // Then
+        assert_eq!(reached, HashSet::from([(0, 0)]));
+    }
+
+    #[parameterized(
+        start = { (0, 0), (2, 0) },
+        expected = { vec![(0, 0)], vec![(0, 0), (1, 0), (2, 0)] })]
+    fn should_enter_conveyor_only_along_its_orientation(
+        start: (usize, usize),
+        expected: Vec<(usize, usize)>,
+    ) {
+        // Given: . < .
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "V".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(
+                1,
+                0,
+                Terrain::Conveyor(Orientation::West),
+            ))],
+        );
+        let my_map = Map::new(3, 1, hash_map);
+
+        // When
+        let mut reached: Vec<(usize, usize)> = my_map.reachable_from(start).into_iter().collect();
+        reached.sort();
+
+        // Then
+        assert_eq!(reached, expected);
+    }
+
+    #[test]
+    fn should_only_stop_at_end_of_ice_slide() {
+        // Given
+        // . = = .
+        // M T M M
+        //
+        // The treasure lies south of an ice cell no one can stop on.
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            [0, 2, 3]
+                .into_iter()
+                .map(|x| Element::RawMountain(RawMountain::new(x, 1)))
+                .collect(),
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(1, 1, 1))],
+        );
+        hash_map.insert(
+            "I".to_string(),
+            [1, 2]
+                .into_iter()
+                .map(|x| Element::RawTerrain(RawTerrain::new(x, 0, Terrain::Ice)))
+                .collect(),
+        );
+        let my_map = Map::new(4, 2, hash_map);
+
+        // When
+        let report = my_map.analyse_reachability(&[(0, 0)]);
+
+        // Then
+        assert_eq!(my_map.neighbours((0, 0)), vec![(3, 0)]);
+        assert!(my_map.reachable_from((0, 0)).contains(&(2, 0)));
+        assert_eq!(report.unreachable_treasures, vec![(1, 1)]);
+    }
+
+    /* SHOULD FIND COMPONENTS */
+
+    #[test]
+    fn should_find_connected_components_of_passable_grid() {
+        // Given
+        let my_map = init_mock_map();
+
+        // When
+        let components = my_map.connected_components();
+
+        // Then
+        let expected = vec![vec![(0, 0)], vec![(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)]];
+        assert_eq!(components, expected);
+    }
+
+    /* SHOULD REPORT UNREACHABLE CELLS */
+
+    #[test]
+    fn should_report_unreachable_treasures_and_plains() {
+        // Given
+        let my_map = init_mock_map();
+
+        // When
+        let report = my_map.analyse_reachability(&[(0, 0)]);
+
+        // Then
+        assert_eq!(report.unreachable_treasures, vec![(2, 0)]);
+        assert_eq!(
+            report.unreachable_plains,
+            vec![(0, 2), (1, 2), (2, 1), (2, 2)]
+        );
+        assert_eq!(report.components.len(), 2);
+    }
+
+    #[test]
+    fn should_only_report_unreachable_cells_of_plain_terrain_as_plains() {
+        // Given
+        let mut hash_map = init_mock_map().to_hashmap(vec![]);
+        hash_map.insert(
+            "S".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(2, 2, Terrain::Swamp))],
+        );
+        let my_map = Map::new(3, 3, hash_map);
+
+        // When
+        let report = my_map.analyse_reachability(&[(0, 0)]);
+
+        // Then
+        assert_eq!(report.unreachable_plains, vec![(0, 2), (1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn should_report_nothing_unreachable_when_every_component_has_a_start() {
+        // Given
+        let my_map = init_mock_map();
+
+        // When
+        let report = my_map.analyse_reachability(&[(0, 0), (2, 2)]);
+
+        // Then
+        assert!(report.unreachable_treasures.is_empty());
+        assert!(report.unreachable_plains.is_empty());
+    }
+}
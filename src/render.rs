@@ -0,0 +1,303 @@
+use std::fmt::Write;
+
+use crate::{
+    element::{Element, Orientation, RawAdventurer, Terrain},
+    grid::Grid,
+    map::Map,
+    observation::Observation,
+};
+
+/// Drawn for the cells an adventurer does not see.
+const FOG: char = '#';
+
+/// Draws the map as ASCII. A square grid is drawn one line per `y` and one
+/// character per `x`. A hex grid is drawn as flat-topped columns, each
+/// column `x` shifted half a cell down from the previous one.
+///
+/// Adventurers are drawn by their orientation (`^ > v <`, and `/ \` for the
+/// diagonals of hex grids), then mountains (`M`), portals (`@`), banks (`B`)
+/// and treasures (their count, `+` above 9), then the terrain: `.` plain,
+/// `~` water, `%` swamp, `=` ice, and a conveyor as the lowercase first
+/// letter of its orientation (`n e s o`).
+///
+/// Edges joined by the topology are marked with `:`, on both sides of each
+/// line when `x` wraps and as a line above and below the map when `y` wraps.
+pub fn render(the_map: &Map, adventurers: &[RawAdventurer]) -> String {
+    draw(the_map, adventurers, &|_| true)
+}
+
+/// Same as `render`, with the cells out of the observation covered by `#`.
+pub fn render_fog(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    observation: &Observation,
+) -> String {
+    draw(the_map, adventurers, &|position| {
+        observation.is_visible(position)
+    })
+}
+
+fn draw(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    visible: &dyn Fn((usize, usize)) -> bool,
+) -> String {
+    let lines = match the_map.grid {
+        Grid::Square => square_lines(the_map, adventurers, visible),
+        Grid::Hex => hex_lines(the_map, adventurers, visible),
+    };
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let side = if the_map.topology.wraps_x() { ":" } else { "" };
+    let edge = format!("{}{}{}\n", side, ":".repeat(width), side);
+
+    let mut drawing = String::new();
+    if the_map.topology.wraps_y() {
+        drawing.push_str(&edge);
+    }
+    for line in lines.iter() {
+        let mut line: String = line.iter().collect();
+        if side.is_empty() {
+            line.truncate(line.trim_end().len());
+        } else {
+            line = format!("{:width$}", line);
+        }
+        drawing.push_str(&format!("{}{}{}\n", side, line, side));
+    }
+    if the_map.topology.wraps_y() {
+        drawing.push_str(&edge);
+    }
+
+    drawing
+}
+
+fn square_lines(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    visible: &dyn Fn((usize, usize)) -> bool,
+) -> Vec<Vec<char>> {
+    (0..the_map.cols)
+        .map(|y| {
+            (0..the_map.rows)
+                .map(|x| cell_symbol(the_map, adventurers, visible, (x, y)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Cell `(x, y)` is drawn on line `2 * y + x` and column `2 * x`, so that
+/// its north and south neighbours are straight above and below it.
+fn hex_lines(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    visible: &dyn Fn((usize, usize)) -> bool,
+) -> Vec<Vec<char>> {
+    let height = (2 * the_map.cols + the_map.rows).saturating_sub(2);
+    let width = (2 * the_map.rows).saturating_sub(1);
+    let mut lines = vec![vec![' '; width]; height];
+
+    for x in 0..the_map.rows {
+        for y in 0..the_map.cols {
+            lines[2 * y + x][2 * x] = cell_symbol(the_map, adventurers, visible, (x, y));
+        }
+    }
+
+    lines
+}
+
+/// Same as `render`, followed by the orientation and the treasure carried
+/// and banked by each adventurer.
+pub fn render_with_legend(the_map: &Map, adventurers: &[RawAdventurer]) -> String {
+    let mut drawing = render(the_map, adventurers);
+
+    for adventurer in adventurers.iter() {
+        let _ = writeln!(
+            drawing,
+            "{} {} {} : {} carried, {} banked",
+            orientation_symbol(adventurer.orientation),
+            adventurer.name,
+            adventurer.get_orientation_to_string(),
+            adventurer.treasure,
+            adventurer.banked
+        );
+    }
+
+    drawing
+}
+
+fn cell_symbol(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    visible: &dyn Fn((usize, usize)) -> bool,
+    position: (usize, usize),
+) -> char {
+    if !visible(position) {
+        return FOG;
+    }
+    if let Some(adventurer) = adventurers
+        .iter()
+        .find(|adventurer| adventurer.position == position)
+    {
+        return orientation_symbol(adventurer.orientation);
+    }
+
+    match the_map.element_at(position) {
+        Some(Element::RawMountain(_)) => 'M',
+        Some(Element::RawPortal(_)) => '@',
+        Some(Element::RawBank(_)) => 'B',
+        Some(Element::RawTreasure(treasure)) if treasure.nb_treasure > 9 => '+',
+        Some(Element::RawTreasure(treasure)) if treasure.nb_treasure > 0 => {
+            char::from_digit(treasure.nb_treasure as u32, 10).unwrap()
+        }
+        _ => terrain_symbol(the_map.terrain_at(position)),
+    }
+}
+
+fn terrain_symbol(terrain: Terrain) -> char {
+    match terrain {
+        Terrain::Plain => '.',
+        Terrain::Water => '~',
+        Terrain::Swamp => '%',
+        Terrain::Ice => '=',
+        Terrain::Conveyor(orientation) => Orientation::from(orientation)
+            .to_lowercase()
+            .chars()
+            .next()
+            .unwrap(),
+    }
+}
+
+fn orientation_symbol(orientation: Orientation) -> char {
+    match orientation {
+        Orientation::North => '^',
+        Orientation::East => '>',
+        Orientation::South => 'v',
+        Orientation::West => '<',
+        Orientation::NorthEast | Orientation::SouthWest => '/',
+        Orientation::SouthEast | Orientation::NorthWest => '\\',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use crate::{
+        element::{RawMountain, RawTerrain, RawTreasure},
+        map::Topology,
+        observation::Sight,
+    };
+
+    use super::*;
+
+    #[test]
+    fn should_render_elements_over_terrain() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(1, 0))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(2, 1, 3))],
+        );
+        hash_map.insert(
+            "W".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(0, 1, Terrain::Water))],
+        );
+        hash_map.insert(
+            "I".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(2, 1, Terrain::Ice))],
+        );
+        hash_map.insert(
+            "V".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(
+                1,
+                1,
+                Terrain::Conveyor(Orientation::West),
+            ))],
+        );
+        let the_map = Map::new(3, 2, hash_map);
+        let adventurers = vec![RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        )];
+
+        // When
+        let drawing = render_with_legend(&the_map, &adventurers);
+
+        // Then
+        assert_eq!(drawing, "vM.\n~o3\nv Lara S : 0 carried, 0 banked\n");
+    }
+
+    #[test]
+    fn should_render_hex_grid_as_shifted_columns() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(1, 0))],
+        );
+        let mut the_map = Map::new(3, 2, hash_map);
+        the_map.grid = Grid::Hex;
+        let adventurers = vec![RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            1,
+            Orientation::NorthEast,
+            "A".to_string(),
+        )];
+
+        // When
+        let drawing = render(&the_map, &adventurers);
+
+        // Then
+        assert_eq!(drawing, ".\n  M\n/   .\n  .\n    .\n");
+    }
+
+    #[test]
+    fn should_cover_cells_out_of_sight_with_fog() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(2, 0))],
+        );
+        let mut the_map = Map::new(5, 2, hash_map);
+        let adventurers = vec![
+            RawAdventurer::new("Lara".to_string(), 1, 0, Orientation::East, String::new()),
+            RawAdventurer::new("Toto".to_string(), 4, 0, Orientation::West, String::new()),
+        ];
+        for adventurer in adventurers.iter() {
+            the_map.occupy(adventurer.position);
+        }
+        let sight = Sight {
+            radius: 3,
+            line_of_sight: true,
+        };
+        let observation = Observation::new(&the_map, &adventurers, 0, sight);
+
+        // When
+        let drawing = render_fog(&the_map, &adventurers, &observation);
+
+        // Then
+        assert_eq!(drawing, ".>M##\n....#\n");
+    }
+
+    #[parameterized(
+        topology = { Topology::Toroidal, Topology::CylinderHorizontal, Topology::CylinderVertical },
+        expected = { ":::::\n:...:\n:::::\n", ":...:\n", ":::\n...\n:::\n" })]
+    fn should_mark_edges_joined_by_topology(topology: Topology, expected: &str) {
+        // Given
+        let mut the_map = Map::new(3, 1, HashMap::new());
+        the_map.topology = topology;
+
+        // When + Then
+        assert_eq!(render(&the_map, &[]), expected);
+    }
+}
@@ -0,0 +1,388 @@
+use std::{fmt::Write, str::FromStr};
+
+use crate::simulation::Simulation;
+
+/// What an adventurer did during a run. An advance is a move when the
+/// adventurer entered at least one cell and blocked otherwise, and a turn is
+/// idle when the adventurer executed no instruction, because it had none
+/// left, was waiting in a swamp or was too slow to play on that turn.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct AdventurerStats {
+    pub moves: usize,
+    pub blocked: usize,
+    pub idle_turns: usize,
+    /// Cells entered, counting each cell of a slide or a conveyor ride.
+    pub distance: usize,
+}
+
+impl AdventurerStats {
+    pub fn record_advance(&mut self, entered_cells: usize) {
+        if entered_cells == 0 {
+            self.blocked += 1;
+        } else {
+            self.moves += 1;
+            self.distance += entered_cells;
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            _ => Err(format!("Unknown report format : {}", raw_value)),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Standing {
+    pub rank: usize,
+    pub name: String,
+    /// Treasure that counts for the ranking: banked when the map has banks,
+    /// carried otherwise.
+    pub score: usize,
+    pub carried: usize,
+    pub banked: usize,
+    pub stats: AdventurerStats,
+}
+
+/// Summary of a run: the adventurers ranked by treasure, the turns played
+/// and the treasure left in the piles of the map.
+#[derive(PartialEq, Debug)]
+pub struct Report {
+    pub standings: Vec<Standing>,
+    pub turns: usize,
+    pub remaining_treasure: usize,
+}
+
+impl Report {
+    /// Adventurers with the same score share a rank, and keep their
+    /// declaration order.
+    pub fn new(simulation: &Simulation) -> Report {
+        let has_banks = simulation.map.has_banks();
+        let mut standings: Vec<Standing> = simulation
+            .adventurers
+            .iter()
+            .zip(simulation.stats.iter())
+            .map(|(adventurer, stats)| Standing {
+                rank: 0,
+                name: adventurer.name.clone(),
+                score: if has_banks {
+                    adventurer.banked
+                } else {
+                    adventurer.treasure
+                },
+                carried: adventurer.treasure,
+                banked: adventurer.banked,
+                stats: stats.clone(),
+            })
+            .collect();
+        standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+
+        for index in 0..standings.len() {
+            standings[index].rank = match index {
+                0 => 1,
+                _ if standings[index].score == standings[index - 1].score => {
+                    standings[index - 1].rank
+                }
+                _ => index + 1,
+            };
+        }
+
+        Report {
+            standings,
+            turns: simulation.turn,
+            remaining_treasure: simulation.map.remaining_treasure(),
+        }
+    }
+
+    pub fn format(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Table => self.to_table(),
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Csv => self.to_csv(),
+        }
+    }
+
+    fn to_table(&self) -> String {
+        let name_width = self
+            .standings
+            .iter()
+            .map(|standing| standing.name.len())
+            .chain(["name".len()])
+            .max()
+            .unwrap_or(0);
+        let mut table = format!(
+            "rank  {:name_width$}  score  carried  banked  moves  blocked  idle  distance\n",
+            "name"
+        );
+
+        for standing in self.standings.iter() {
+            let _ = writeln!(
+                table,
+                "{:>4}  {:name_width$}  {:>5}  {:>7}  {:>6}  {:>5}  {:>7}  {:>4}  {:>8}",
+                standing.rank,
+                standing.name,
+                standing.score,
+                standing.carried,
+                standing.banked,
+                standing.stats.moves,
+                standing.stats.blocked,
+                standing.stats.idle_turns,
+                standing.stats.distance
+            );
+        }
+        let _ = writeln!(table, "turns : {}", self.turns);
+        let _ = writeln!(table, "remaining treasure : {}", self.remaining_treasure);
+
+        table
+    }
+
+    fn to_json(&self) -> String {
+        let standings: Vec<String> = self
+            .standings
+            .iter()
+            .map(|standing| {
+                format!(
+                    "{{\"rank\":{},\"name\":\"{}\",\"score\":{},\"carried\":{},\"banked\":{},\"moves\":{},\"blocked\":{},\"idle_turns\":{},\"distance\":{}}}",
+                    standing.rank,
+                    escape_json(&standing.name),
+                    standing.score,
+                    standing.carried,
+                    standing.banked,
+                    standing.stats.moves,
+                    standing.stats.blocked,
+                    standing.stats.idle_turns,
+                    standing.stats.distance
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"standings\":[{}],\"turns\":{},\"remaining_treasure\":{}}}\n",
+            standings.join(","),
+            self.turns,
+            self.remaining_treasure
+        )
+    }
+
+    /// One line per adventurer, each repeating the totals of the run in its
+    /// last columns.
+    fn to_csv(&self) -> String {
+        let mut csv = "rank,name,score,carried,banked,moves,blocked,idle_turns,distance,turns,remaining_treasure\n"
+            .to_string();
+
+        for standing in self.standings.iter() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                standing.rank,
+                escape_csv(&standing.name),
+                standing.score,
+                standing.carried,
+                standing.banked,
+                standing.stats.moves,
+                standing.stats.blocked,
+                standing.stats.idle_turns,
+                standing.stats.distance,
+                self.turns,
+                self.remaining_treasure
+            );
+        }
+
+        csv
+    }
+}
+
+/// Quotes a field holding a separator, a quote or a line break, doubling
+/// its quotes.
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_json(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use crate::{
+        element::{Element, Orientation, RawAdventurer, RawMountain, RawTreasure},
+        map::Map,
+    };
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    // L . T T
+    // M . . I
+    //
+    // Lara takes the last treasure of the east pile while Indiana walks
+    // away from it.
+    fn init_mock_simulation() -> Simulation {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(0, 1))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![
+                Element::RawTreasure(RawTreasure::new(2, 0, 2)),
+                Element::RawTreasure(RawTreasure::new(3, 0, 2)),
+            ],
+        );
+
+        let mut simulation = Simulation::new(
+            Map::new(4, 2, hash_map),
+            vec![
+                RawAdventurer::new(
+                    "Indiana".to_string(),
+                    3,
+                    1,
+                    Orientation::North,
+                    "AGGAGGA".to_string(),
+                ),
+                RawAdventurer::new(
+                    "Lara".to_string(),
+                    0,
+                    0,
+                    Orientation::South,
+                    "AGAAA".to_string(),
+                ),
+            ],
+        );
+        simulation.run();
+
+        simulation
+    }
+
+    /* SHOULD BUILD REPORT */
+
+    #[test]
+    fn should_rank_adventurers_and_count_what_they_did() {
+        // Given
+        let simulation = init_mock_simulation();
+
+        // When
+        let report = Report::new(&simulation);
+
+        // Then
+        assert_eq!(
+            report,
+            Report {
+                standings: vec![
+                    Standing {
+                        rank: 1,
+                        name: "Lara".to_string(),
+                        score: 2,
+                        carried: 2,
+                        banked: 0,
+                        stats: AdventurerStats {
+                            moves: 3,
+                            blocked: 1,
+                            idle_turns: 2,
+                            distance: 3,
+                        },
+                    },
+                    Standing {
+                        rank: 2,
+                        name: "Indiana".to_string(),
+                        score: 1,
+                        carried: 1,
+                        banked: 0,
+                        stats: AdventurerStats {
+                            moves: 2,
+                            blocked: 1,
+                            idle_turns: 0,
+                            distance: 2,
+                        },
+                    },
+                ],
+                turns: 7,
+                remaining_treasure: 1,
+            }
+        );
+    }
+
+    /* SHOULD FORMAT REPORT */
+
+    #[test]
+    fn should_format_report_as_csv() {
+        // Given
+        let report = Report::new(&init_mock_simulation());
+
+        // When
+        let csv = report.format(ReportFormat::Csv);
+
+        // Then
+        assert_eq!(
+            csv,
+            "rank,name,score,carried,banked,moves,blocked,idle_turns,distance,turns,remaining_treasure\n\
+             1,Lara,2,2,0,3,1,2,3,7,1\n\
+             2,Indiana,1,1,0,2,1,0,2,7,1\n"
+        );
+    }
+
+    #[parameterized(
+        name = { "Lara", "Lara, Croft", "Lara \"Tomb\" Raider" },
+        expected = { "Lara", "\"Lara, Croft\"", "\"Lara \"\"Tomb\"\" Raider\"" })]
+    fn should_quote_csv_names_with_separators_or_quotes(name: &str, expected: &str) {
+        assert_eq!(escape_csv(name), expected);
+    }
+
+    #[test]
+    fn should_format_report_as_json() {
+        // Given
+        let report = Report::new(&init_mock_simulation());
+
+        // When
+        let json = report.format(ReportFormat::Json);
+
+        // Then
+        assert!(json.starts_with("{\"standings\":[{\"rank\":1,\"name\":\"Lara\",\"score\":2,"));
+        assert!(json.ends_with("],\"turns\":7,\"remaining_treasure\":1}\n"));
+    }
+
+    #[test]
+    fn should_format_report_as_table() {
+        // Given
+        let report = Report::new(&init_mock_simulation());
+
+        // When
+        let table = report.format(ReportFormat::Table);
+
+        // Then
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines[0],
+            "rank  name     score  carried  banked  moves  blocked  idle  distance"
+        );
+        assert_eq!(
+            lines[2],
+            "   2  Indiana      1        1       0      2        1     0         2"
+        );
+        assert_eq!(lines[3..], ["turns : 7", "remaining treasure : 1"]);
+    }
+}
@@ -0,0 +1,92 @@
+/// Small deterministic pseudo-random generator (SplitMix64), so that a seed
+/// always gives the same scenario whatever the platform.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Internal state, which the generator never comes back to.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`. `bound` must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform value in `min..=max`.
+    pub fn between(&mut self, min: usize, max: usize) -> usize {
+        min + self.below(max - min + 1)
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, values: &'a [T]) -> &'a T {
+        &values[self.below(values.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for index in (1..values.len()).rev() {
+            values.swap(index, self.below(index + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_give_same_values_for_same_seed() {
+        // Given
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+
+        // When + Then
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn should_stay_within_bounds() {
+        // Given
+        let mut rng = Rng::new(7);
+
+        // When + Then
+        for _ in 0..100 {
+            assert!(rng.below(3) < 3);
+            assert!((2..=5).contains(&rng.between(2, 5)));
+        }
+    }
+
+    #[test]
+    fn should_keep_every_value_when_shuffling() {
+        // Given
+        let mut rng = Rng::new(3);
+        let mut values: Vec<usize> = (0..20).collect();
+
+        // When
+        rng.shuffle(&mut values);
+        values.sort();
+
+        // Then
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,363 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    controller::Instruction,
+    element::Terrain,
+    observation::{CellContent, CellView, Observation},
+};
+
+/// Prefix of the pattern field of an adventurer line holding a script
+/// instead of a pattern.
+pub static SCRIPT_PREFIX: &str = "script:";
+
+/// Steps a script may take to choose an instruction when no budget is given.
+pub const DEFAULT_BUDGET: usize = 64;
+
+/// Cell next to an adventurer, relative to where it faces.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum RelativeCell {
+    Here,
+    Ahead,
+    Left,
+    Right,
+    Behind,
+}
+
+impl FromStr for RelativeCell {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "here" => Ok(RelativeCell::Here),
+            "ahead" => Ok(RelativeCell::Ahead),
+            "left" => Ok(RelativeCell::Left),
+            "right" => Ok(RelativeCell::Right),
+            "behind" => Ok(RelativeCell::Behind),
+            _ => Err(format!("Unknown cell : {}", raw_value)),
+        }
+    }
+}
+
+/// What a script can tell about a cell. Cells off the map or out of sight
+/// are `edge` and `blocked`, and `free` cells are the ones that are not
+/// `blocked`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum CellKind {
+    Free,
+    Blocked,
+    Edge,
+    Mountain,
+    Treasure,
+    Bank,
+    Portal,
+    Adventurer,
+    Water,
+    Swamp,
+    Ice,
+    Conveyor,
+}
+
+impl FromStr for CellKind {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "free" => Ok(CellKind::Free),
+            "blocked" => Ok(CellKind::Blocked),
+            "edge" => Ok(CellKind::Edge),
+            "mountain" => Ok(CellKind::Mountain),
+            "treasure" => Ok(CellKind::Treasure),
+            "bank" => Ok(CellKind::Bank),
+            "portal" => Ok(CellKind::Portal),
+            "adventurer" => Ok(CellKind::Adventurer),
+            "water" => Ok(CellKind::Water),
+            "swamp" => Ok(CellKind::Swamp),
+            "ice" => Ok(CellKind::Ice),
+            "conveyor" => Ok(CellKind::Conveyor),
+            _ => Err(format!("Unknown cell kind : {}", raw_value)),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Condition {
+    cell: RelativeCell,
+    kind: CellKind,
+    negated: bool,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        let (cell, kind, negated) = match raw_value.split_once("!=") {
+            Some((cell, kind)) => (cell, kind, true),
+            None => match raw_value.split_once('=') {
+                Some((cell, kind)) => (cell, kind, false),
+                None => return Err(format!("Invalid condition : {}", raw_value)),
+            },
+        };
+
+        Ok(Condition {
+            cell: cell.parse()?,
+            kind: kind.parse()?,
+            negated,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Rule {
+    conditions: Vec<Condition>,
+    instruction: Instruction,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        let (conditions, instruction) = match raw_value.split_once('?') {
+            Some((conditions, instruction)) => (
+                conditions
+                    .split('&')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?,
+                instruction,
+            ),
+            None => (Vec::new(), raw_value),
+        };
+
+        Ok(Rule {
+            conditions,
+            instruction: instruction.parse()?,
+        })
+    }
+}
+
+/// Behaviour of an adventurer choosing its instruction each turn from the
+/// cells around it, instead of playing a fixed pattern.
+///
+/// A script is a list of rules separated by `;`, tried in order. A rule is
+/// an instruction (`A`, `G`, `D` or `W` to wait), preceded by conditions
+/// joined with `&` and a `?` when it only applies to some turns. A condition
+/// compares a cell (`here`, `ahead`, `left`, `right` or `behind`) with a kind
+/// of cell using `=` or `!=`. `ahead=blocked?D;A` walks forward until
+/// blocked, then turns right. The adventurer waits when no rule applies.
+///
+/// Scripts only get the observation of their adventurer. Each rule tried and
+/// each condition tested costs one step, and a script running out of steps
+/// waits for this turn.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Script {
+    source: String,
+    rules: Vec<Rule>,
+}
+
+impl Script {
+    /// Instruction chosen from what the adventurer sees, within `budget`
+    /// steps.
+    pub fn decide(&self, observation: &Observation, budget: usize) -> Instruction {
+        let mut steps = 0;
+        for rule in self.rules.iter() {
+            steps += 1;
+            if steps > budget {
+                return Instruction::Wait;
+            }
+
+            let mut applies = true;
+            for condition in rule.conditions.iter() {
+                steps += 1;
+                if steps > budget {
+                    return Instruction::Wait;
+                }
+                if !Self::holds(condition, observation) {
+                    applies = false;
+                    break;
+                }
+            }
+            if applies {
+                return rule.instruction;
+            }
+        }
+
+        Instruction::Wait
+    }
+
+    fn holds(condition: &Condition, observation: &Observation) -> bool {
+        let cell = Self::cell(condition.cell, observation);
+
+        Self::is_of_kind(condition.kind, cell) != condition.negated
+    }
+
+    /// Cell next to the adventurer, if it is seen.
+    fn cell(cell: RelativeCell, observation: &Observation) -> Option<&CellView> {
+        let geometry = observation.geometry();
+        let orientation = observation.orientation;
+        let towards = match cell {
+            RelativeCell::Here => return observation.here(),
+            RelativeCell::Ahead => orientation,
+            RelativeCell::Left => geometry.turn(orientation, false),
+            RelativeCell::Right => geometry.turn(orientation, true),
+            RelativeCell::Behind => (0..geometry.orientations().len() / 2)
+                .fold(orientation, |orientation, _| {
+                    geometry.turn(orientation, true)
+                }),
+        };
+
+        observation.towards(towards)
+    }
+
+    fn is_of_kind(kind: CellKind, cell: Option<&CellView>) -> bool {
+        let Some(cell) = cell else {
+            return matches!(kind, CellKind::Edge | CellKind::Blocked);
+        };
+
+        match kind {
+            CellKind::Free => cell.is_free(),
+            CellKind::Blocked => !cell.is_free(),
+            CellKind::Edge => false,
+            CellKind::Mountain => cell.content == CellContent::Mountain,
+            CellKind::Treasure => cell.treasure() > 0,
+            CellKind::Bank => cell.content == CellContent::Bank,
+            CellKind::Portal => cell.content == CellContent::Portal,
+            CellKind::Adventurer => cell.adventurer.is_some(),
+            CellKind::Water => cell.terrain == Terrain::Water,
+            CellKind::Swamp => cell.terrain == Terrain::Swamp,
+            CellKind::Ice => cell.terrain == Terrain::Ice,
+            CellKind::Conveyor => matches!(cell.terrain, Terrain::Conveyor(_)),
+        }
+    }
+}
+
+impl FromStr for Script {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        let source: String = raw_value.split_whitespace().collect();
+        let rules = source
+            .split(';')
+            .filter(|rule| !rule.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Rule>, _>>()?;
+        if rules.is_empty() {
+            return Err("Empty script".to_string());
+        }
+
+        Ok(Script { source, rules })
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::{
+        element::{Element, Orientation, RawAdventurer, RawMountain, RawTerrain, RawTreasure},
+        map::Map,
+        observation::Sight,
+    };
+
+    /* MOCK INIT */
+
+    /// Adventurer in the middle of a map of 3 x 3 facing north, with a
+    /// mountain north of it, a treasure east of it and water south of it.
+    fn init_mock_observation() -> Observation {
+        let mut content = HashMap::new();
+        content.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(1, 0))],
+        );
+        content.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(2, 1, 1))],
+        );
+        content.insert(
+            "W".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(1, 2, Terrain::Water))],
+        );
+        let mut the_map = Map::new(3, 3, content);
+        the_map.occupy((1, 1));
+        let adventurer =
+            RawAdventurer::new("Lara".to_string(), 1, 1, Orientation::North, String::new());
+
+        Observation::new(&the_map, &[adventurer], 0, Sight::default())
+    }
+
+    /* SHOULD PARSE SCRIPT */
+
+    #[test]
+    fn should_keep_source_without_whitespace() {
+        let script: Script = "ahead = blocked ? D ; A".parse().unwrap();
+
+        assert_eq!(script.to_string(), "ahead=blocked?D;A");
+    }
+
+    #[parameterized(
+        source = { "", "ahead=blocked", "ahead=wall?D", "front=free?A", "ahead~free?A", "ahead=free?X" },
+        expected = {
+            "Empty script",
+            "Unknown instruction : ahead=blocked",
+            "Unknown cell kind : wall",
+            "Unknown cell : front",
+            "Invalid condition : ahead~free",
+            "Unknown instruction : X"
+        })]
+    fn should_reject_invalid_script(source: &str, expected: &str) {
+        assert_eq!(source.parse::<Script>(), Err(expected.to_string()));
+    }
+
+    /* SHOULD DECIDE */
+
+    #[parameterized(
+        source = {
+            "ahead=blocked?D;A",
+            "ahead=mountain&right=treasure?D;A",
+            "behind=water?G;A",
+            "left=edge?W;A",
+            "left!=edge?W;A",
+            "here=adventurer&ahead=free?A;G",
+            "ahead=free?A"
+        },
+        expected = {
+            Instruction::TurnRight,
+            Instruction::TurnRight,
+            Instruction::TurnLeft,
+            Instruction::Advance,
+            Instruction::Wait,
+            Instruction::TurnLeft,
+            Instruction::Wait
+        })]
+    fn should_play_first_rule_whose_conditions_hold(source: &str, expected: Instruction) {
+        // Given
+        let observation = init_mock_observation();
+        let script: Script = source.parse().unwrap();
+
+        // When
+        let instruction = script.decide(&observation, DEFAULT_BUDGET);
+
+        // Then
+        assert_eq!(instruction, expected);
+    }
+
+    #[parameterized(budget = { 0, 3, 4 }, expected = { Instruction::Wait, Instruction::Wait, Instruction::TurnRight })]
+    fn should_wait_when_budget_runs_out(budget: usize, expected: Instruction) {
+        // Given
+        let observation = init_mock_observation();
+        let script: Script = "ahead=free?A;right=treasure?D".parse().unwrap();
+
+        // When
+        let instruction = script.decide(&observation, budget);
+
+        // Then
+        assert_eq!(instruction, expected);
+    }
+}
@@ -0,0 +1,2059 @@
+use std::{collections::BTreeMap, collections::HashMap, str::FromStr};
+
+use crate::{
+    controller::{Controller, ControllerKind, Instruction},
+    element::{Element, Orientation, RawAdventurer, RawMap, Terrain},
+    grid::Geometry,
+    map::Map,
+    observation::{Observation, Sight},
+    report::AdventurerStats,
+    rng::Rng,
+    script::DEFAULT_BUDGET,
+};
+
+/// Turns after which a run without turn limit stops when an adventurer is
+/// driven by another controller than its pattern: a random walk never comes
+/// back to an earlier state, so it would never stop otherwise.
+pub const DEFAULT_MAX_TURNS: usize = 1000;
+
+/// Moves the adventurer of `(dx, dy)`, then lets the terrain of the entered
+/// cell act on it: ice keeps it sliding the same way, a conveyor carries it
+/// along its orientation and a swamp makes it wait a turn.
+pub fn update_position(
+    the_map: &mut Map,
+    mut adventurer: RawAdventurer,
+    new_x: isize,
+    new_y: isize,
+) -> RawAdventurer {
+    move_adventurer(the_map, &mut adventurer, new_x, new_y);
+    adventurer
+}
+
+/// Same as `update_position` on the adventurer in place, returning the
+/// number of cells entered.
+fn move_adventurer(
+    the_map: &mut Map,
+    adventurer: &mut RawAdventurer,
+    new_x: isize,
+    new_y: isize,
+) -> usize {
+    let mut entered_cells = 0;
+    let (mut dx, mut dy) = (new_x, new_y);
+
+    // Bounds slides and conveyor chains, which could loop forever
+    for _ in 0..the_map.rows * the_map.cols {
+        if dx == 0 && dy == 0 || !enter_cell(the_map, adventurer, dx, dy) {
+            break;
+        }
+        entered_cells += 1;
+
+        match the_map.terrain_at(adventurer.position) {
+            Terrain::Ice => (),
+            Terrain::Conveyor(orientation) => {
+                (dx, dy, _) = next_move(the_map.geometry(), orientation, 'A')
+            }
+            Terrain::Swamp => {
+                adventurer.waiting += 1;
+                break;
+            }
+            Terrain::Plain | Terrain::Water => break,
+        }
+    }
+
+    entered_cells
+}
+
+/// Moves the adventurer one cell, then collects the treasure there if it can
+/// carry more, drops its load on a bank or takes the portal there when its
+/// other end is free. The move fails, leaving the adventurer unchanged, when
+/// the cell is off the map, impassable, occupied or a conveyor entered
+/// against its orientation.
+fn enter_cell(the_map: &mut Map, adventurer: &mut RawAdventurer, dx: isize, dy: isize) -> bool {
+    let Some(target) = the_map.entry(adventurer.position, dx, dy) else {
+        return false;
+    };
+    if the_map.is_occupied(target) {
+        return false;
+    }
+
+    the_map.move_occupant(adventurer.position, target);
+    adventurer.position = target;
+
+    match the_map.element_at(target) {
+        Some(Element::RawTreasure(treasure))
+            if treasure.nb_treasure > 0 && adventurer.can_carry() =>
+        {
+            Map::update_position(the_map, target.0, target.1);
+            adventurer.update_treasure();
+        }
+        Some(Element::RawBank(_)) => adventurer.bank(),
+        Some(Element::RawPortal(portal))
+            if !the_map.is_occupied(portal.destination)
+                && the_map.is_passable(portal.destination) =>
+        {
+            let destination = portal.destination;
+            the_map.move_occupant(target, destination);
+            adventurer.position = destination;
+        }
+        _ => (),
+    }
+
+    true
+}
+
+pub fn get_sequence(pattern: &str) -> (char, Vec<char>) {
+    let chars: Vec<char> = pattern.chars().collect();
+    if let Some((first, rest)) = chars.split_first() {
+        return (*first, rest.to_vec());
+    }
+
+    panic!("Input pattern is empty");
+}
+
+/// Moves of the game: the cell offset and the new orientation of an
+/// adventurer facing `orientation` when it plays `action` on a grid of the
+/// given geometry.
+pub fn next_move(
+    geometry: &dyn Geometry,
+    orientation: Orientation,
+    action: char,
+) -> (isize, isize, Orientation) {
+    match action {
+        'A' => {
+            let (dx, dy) = geometry.offset(orientation).unwrap_or((0, 0));
+            (dx, dy, orientation)
+        }
+        'G' => (0, 0, geometry.turn(orientation, false)),
+        'D' => (0, 0, geometry.turn(orientation, true)),
+        _ => (0, 0, orientation),
+    }
+}
+
+pub fn update_adventurer(mut adventurer: RawAdventurer, the_map: &mut Map) -> RawAdventurer {
+    let action = (!adventurer.is_finished()).then(|| {
+        let mut controller = adventurer.controller.build(DEFAULT_BUDGET);
+        let observation = observe(
+            the_map,
+            std::slice::from_ref(&adventurer),
+            0,
+            Sight::default(),
+            controller.as_ref(),
+        );
+        controller.decide(&observation).to_char()
+    });
+    play_instruction(&mut adventurer, the_map, action);
+    adventurer
+}
+
+/// What the controller of the adventurer at `index` is given to decide.
+fn observe(
+    the_map: &Map,
+    adventurers: &[RawAdventurer],
+    index: usize,
+    sight: Sight,
+    controller: &dyn Controller,
+) -> Observation {
+    if controller.needs_observation() {
+        Observation::new(the_map, adventurers, index, sight)
+    } else {
+        Observation::blind(&adventurers[index], the_map.grid)
+    }
+}
+
+/// Same as `update_adventurer` on the adventurer in place with the action
+/// chosen for it, returning the number of cells entered.
+fn play_instruction(
+    adventurer: &mut RawAdventurer,
+    the_map: &mut Map,
+    action: Option<char>,
+) -> usize {
+    if adventurer.waiting > 0 {
+        adventurer.waiting -= 1;
+        return 0;
+    }
+
+    let action = action.unwrap_or_else(|| panic!("Input pattern is empty"));
+    adventurer.skip_instruction();
+
+    let (dx, dy, new_orientation) = next_move(the_map.geometry(), adventurer.orientation, action);
+    let entered_cells = move_adventurer(the_map, adventurer, dx, dy);
+    adventurer.orientation = new_orientation;
+
+    entered_cells
+}
+
+/// Who enters a cell that several adventurers want to enter on the same turn
+/// in simultaneous mode.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ConflictPolicy {
+    BothBlocked,
+    PriorityByName,
+    Random(u64),
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value.split_once(':') {
+            None if raw_value == "blocked" => Ok(ConflictPolicy::BothBlocked),
+            None if raw_value == "name" => Ok(ConflictPolicy::PriorityByName),
+            None if raw_value == "random" => Ok(ConflictPolicy::Random(0)),
+            Some(("random", seed)) => seed
+                .parse()
+                .map(ConflictPolicy::Random)
+                .map_err(|_| format!("Invalid seed : {}", seed)),
+            _ => Err(format!("Unknown conflict policy : {}", raw_value)),
+        }
+    }
+}
+
+/// How the moves of a turn are resolved.
+///
+/// `Sequential` plays adventurers in declaration order, so an earlier
+/// adventurer always wins a contested cell. `Simultaneous` lets every
+/// adventurer choose its move from the same state: two adventurers swapping
+/// places are both blocked, several entering the same cell are settled by
+/// the conflict policy, and an adventurer moving into the cell of one that
+/// stays is blocked. Only the first cell of a move is contested: the rest of
+/// a slide, a conveyor ride or a portal jump stops before any cell that is
+/// occupied or entered by another adventurer on this turn.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ResolutionMode {
+    Sequential,
+    Simultaneous(ConflictPolicy),
+}
+
+/// How a run ended.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RunOutcome {
+    /// Every adventurer played its whole pattern.
+    Finished,
+    /// The state of the simulation at `first_turn` came back every `period`
+    /// turns, so the run would never end nor change the score.
+    CycleDetected { period: usize, first_turn: usize },
+    /// The run was stopped at the maximum number of turns.
+    TurnLimitReached,
+}
+
+/// What a turn depends on: two turns starting from equal states play the
+/// same. Treasure on the map is left out, as it only changes when the
+/// treasure of an adventurer does.
+#[derive(PartialEq, Eq, Clone)]
+struct State {
+    adventurers: Vec<AdventurerState>,
+    controllers: Vec<u64>,
+    commands: Vec<Option<Instruction>>,
+    rng: Rng,
+}
+
+/// Position, orientation, cursor, waiting turns, carried and banked
+/// treasure, and turn in the speed period.
+type AdventurerState = (
+    (usize, usize),
+    Orientation,
+    usize,
+    usize,
+    usize,
+    usize,
+    usize,
+);
+
+/// Runs the adventurers of a scenario on its map, one turn at a time. A cell
+/// can only hold one adventurer.
+#[derive(Clone)]
+pub struct Simulation {
+    pub map: Map,
+    pub adventurers: Vec<RawAdventurer>,
+    pub resolution: ResolutionMode,
+    pub turn: usize,
+    /// What each adventurer did so far, in declaration order.
+    pub stats: Vec<AdventurerStats>,
+    /// Adventurers start their pattern over once they played all of it.
+    pub looping: bool,
+    /// Turn at which `run` stops, if any.
+    pub max_turns: Option<usize>,
+    /// Steps a script may take to choose each instruction.
+    pub script_budget: usize,
+    /// What adventurers see of the map.
+    pub sight: Sight,
+    /// Controller of each adventurer, built when the simulation is created.
+    controllers: Vec<Box<dyn Controller>>,
+    /// Last instruction commanded from outside the simulation to each
+    /// adventurer, which its observation carries.
+    commands: Vec<Option<Instruction>>,
+    rng: Rng,
+}
+
+impl Simulation {
+    pub fn new(mut map: Map, adventurers: Vec<RawAdventurer>) -> Simulation {
+        for adventurer in adventurers.iter() {
+            map.occupy(adventurer.position);
+        }
+
+        Simulation {
+            map,
+            stats: vec![AdventurerStats::default(); adventurers.len()],
+            controllers: Self::build_controllers(&adventurers, DEFAULT_BUDGET),
+            commands: vec![None; adventurers.len()],
+            adventurers,
+            resolution: ResolutionMode::Sequential,
+            turn: 0,
+            looping: false,
+            max_turns: None,
+            script_budget: DEFAULT_BUDGET,
+            sight: Sight::default(),
+            rng: Rng::new(0),
+        }
+    }
+
+    pub fn with_looping(self, looping: bool) -> Simulation {
+        Simulation { looping, ..self }
+    }
+
+    pub fn with_max_turns(self, max_turns: Option<usize>) -> Simulation {
+        Simulation { max_turns, ..self }
+    }
+
+    pub fn with_sight(self, sight: Sight) -> Simulation {
+        Simulation { sight, ..self }
+    }
+
+    pub fn with_script_budget(self, script_budget: usize) -> Simulation {
+        Simulation {
+            script_budget,
+            controllers: Self::build_controllers(&self.adventurers, script_budget),
+            ..self
+        }
+    }
+
+    fn build_controllers(
+        adventurers: &[RawAdventurer],
+        script_budget: usize,
+    ) -> Vec<Box<dyn Controller>> {
+        adventurers
+            .iter()
+            .map(|adventurer| adventurer.controller.build(script_budget))
+            .collect()
+    }
+
+    /// Drives the adventurer at `index` by `pattern` from its first
+    /// instruction.
+    pub fn set_pattern(&mut self, index: usize, pattern: String) {
+        let adventurer = &mut self.adventurers[index];
+        adventurer.set_pattern(pattern);
+        self.controllers[index] = adventurer.controller.build(self.script_budget);
+    }
+
+    /// Gives the adventurer at `index` an instruction from outside the
+    /// simulation, which only external controllers follow.
+    pub fn command(&mut self, index: usize, instruction: Instruction) {
+        self.commands[index] = Some(instruction);
+    }
+
+    pub fn with_resolution(self, resolution: ResolutionMode) -> Simulation {
+        let seed = match resolution {
+            ResolutionMode::Simultaneous(ConflictPolicy::Random(seed)) => seed,
+            _ => 0,
+        };
+
+        Simulation {
+            resolution,
+            rng: Rng::new(seed),
+            ..self
+        }
+    }
+
+    pub fn from_hashmap(content: &HashMap<String, Vec<Element>>) -> Simulation {
+        let map_size: Vec<RawMap> = content
+            .get("C")
+            .map(|raw_maps| {
+                raw_maps
+                    .iter()
+                    .filter_map(|element| {
+                        if let Element::RawMap(raw_map) = element {
+                            Some(raw_map.clone())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_else(|| panic!("No map size found"));
+
+        let adventurers: Vec<RawAdventurer> = content
+            .get("A")
+            .map(|adventurers| {
+                adventurers
+                    .iter()
+                    .filter_map(|element| {
+                        if let Element::RawAdventurer(adventurer) = element {
+                            Some(adventurer.clone())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_else(|| panic!("No adventurers found"));
+
+        // The map has no use for the adventurers and their patterns
+        let map_content = content
+            .iter()
+            .filter(|(key, _)| key.as_str() != "A")
+            .map(|(key, elements)| (key.clone(), elements.clone()))
+            .collect();
+        let the_map = Map::new(map_size[0].position.0, map_size[0].position.1, map_content);
+
+        Simulation::new(the_map, adventurers)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.adventurers.iter().all(|adventurer| {
+            adventurer.is_finished() && (!self.looping || adventurer.pattern.is_empty())
+        })
+    }
+
+    /// Whether the run may go on forever, coming back to earlier states.
+    fn can_cycle(&self) -> bool {
+        self.looping || self.has_controllers()
+    }
+
+    /// Whether an adventurer is driven by something else than its pattern.
+    fn has_controllers(&self) -> bool {
+        self.adventurers
+            .iter()
+            .any(|adventurer| adventurer.controller != ControllerKind::Pattern)
+    }
+
+    fn rewind_finished_patterns(&mut self) {
+        if !self.looping {
+            return;
+        }
+        for adventurer in self.adventurers.iter_mut() {
+            if adventurer.is_finished() {
+                adventurer.cursor = 0;
+            }
+        }
+    }
+
+    /// Plays one turn: every adventurer with instructions left executes as
+    /// many as its speed allows on this turn. Returns `false` when there was
+    /// nothing left to play.
+    ///
+    /// Adventurers executing several instructions play them in phases: every
+    /// adventurer acting in a phase executes one instruction before any of
+    /// them executes the next one.
+    pub fn step(&mut self) -> bool {
+        if self.is_done() {
+            return false;
+        }
+
+        let budgets: Vec<usize> = self
+            .adventurers
+            .iter()
+            .map(|adventurer| adventurer.speed.actions_on_turn(self.turn))
+            .collect();
+        let phases = budgets.iter().copied().max().unwrap_or(0);
+        let mut played = vec![false; self.adventurers.len()];
+
+        for phase in 0..phases {
+            self.rewind_finished_patterns();
+            let acting: Vec<bool> = self
+                .adventurers
+                .iter()
+                .zip(budgets.iter())
+                .map(|(adventurer, budget)| *budget > phase && !adventurer.is_finished())
+                .collect();
+            // Waiting adventurers act without playing an instruction
+            for ((adventurer, is_acting), played) in self
+                .adventurers
+                .iter()
+                .zip(acting.iter())
+                .zip(played.iter_mut())
+            {
+                *played |= *is_acting && adventurer.waiting == 0;
+            }
+
+            match self.resolution {
+                ResolutionMode::Sequential => self.step_sequential(&acting),
+                ResolutionMode::Simultaneous(policy) => self.step_simultaneous(&acting, policy),
+            }
+        }
+        for (stats, played) in self.stats.iter_mut().zip(played) {
+            if !played {
+                stats.idle_turns += 1;
+            }
+        }
+        self.turn += 1;
+
+        true
+    }
+
+    fn step_sequential(&mut self, acting: &[bool]) {
+        for (index, is_acting) in acting.iter().enumerate() {
+            if *is_acting {
+                let action = self.decide(index);
+                self.play(index, action);
+            }
+        }
+    }
+
+    /// Instruction the controller of the adventurer chooses for it, `None`
+    /// while it waits.
+    fn decide(&mut self, index: usize) -> Option<char> {
+        if self.adventurers[index].waiting > 0 {
+            return None;
+        }
+
+        let controller = self.controllers[index].as_mut();
+        let mut observation = observe(&self.map, &self.adventurers, index, self.sight, controller);
+        observation.command = self.commands[index];
+        Some(controller.decide(&observation).to_char())
+    }
+
+    /// Plays the action chosen for an adventurer and records it in its
+    /// stats.
+    fn play(&mut self, index: usize, action: Option<char>) {
+        let adventurer = &mut self.adventurers[index];
+        let advances = adventurer.waiting == 0 && action == Some('A');
+
+        let entered_cells = play_instruction(adventurer, &mut self.map, action);
+
+        if advances {
+            self.stats[index].record_advance(entered_cells);
+        }
+    }
+
+    fn step_simultaneous(&mut self, acting: &[bool], policy: ConflictPolicy) {
+        // Every adventurer chooses before any of them moves
+        let mut actions: Vec<Option<char>> = vec![None; self.adventurers.len()];
+        for (index, is_acting) in acting.iter().enumerate() {
+            if *is_acting {
+                actions[index] = self.decide(index);
+            }
+        }
+        let targets: Vec<Option<(usize, usize)>> = self
+            .adventurers
+            .iter()
+            .zip(actions.iter())
+            .map(|(adventurer, action)| self.wanted_cell(adventurer, (*action)?))
+            .collect();
+        let moving = self.resolve_moves(&targets, policy);
+
+        // Only the first cell of a move is contested: the cells the movers
+        // enter are reserved for them, so that slides, conveyor rides and
+        // portal jumps stop before them as before any occupied cell
+        let reserved: HashMap<(usize, usize), usize> = targets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, target)| Some((target.filter(|_| moving[index])?, index)))
+            .collect();
+        for cell in reserved.keys() {
+            self.map.occupy(*cell);
+        }
+
+        for (index, is_moving) in moving.iter().enumerate() {
+            if !acting[index] {
+                continue;
+            }
+
+            if targets[index].is_some() && !is_moving {
+                self.adventurers[index].skip_instruction();
+                self.stats[index].record_advance(0);
+                continue;
+            }
+
+            let start = self.adventurers[index].position;
+            if let (Some(target), true) = (targets[index], is_moving) {
+                // Its adventurer, if any, leaves it later in this phase
+                self.map.vacate(target);
+            }
+            self.play(index, actions[index]);
+            // Leaving vacated the cell, which may be reserved for or already
+            // entered by the adventurer following this one
+            if let (Some(&follower), true) = (reserved.get(&start), is_moving) {
+                if follower > index || self.adventurers[follower].position == start {
+                    self.map.occupy(start);
+                }
+            }
+        }
+    }
+
+    /// Cell the adventurer tries to enter this turn, if its action is an
+    /// advance towards a cell it can enter.
+    fn wanted_cell(&self, adventurer: &RawAdventurer, action: char) -> Option<(usize, usize)> {
+        let (dx, dy, _) = next_move(self.map.geometry(), adventurer.orientation, action);
+        if dx == 0 && dy == 0 {
+            return None;
+        }
+
+        self.map.entry(adventurer.position, dx, dy)
+    }
+
+    fn resolve_moves(
+        &mut self,
+        targets: &[Option<(usize, usize)>],
+        policy: ConflictPolicy,
+    ) -> Vec<bool> {
+        let positions: Vec<(usize, usize)> = self
+            .adventurers
+            .iter()
+            .map(|adventurer| adventurer.position)
+            .collect();
+        let mut moving: Vec<bool> = targets.iter().map(Option::is_some).collect();
+        let by_position: HashMap<(usize, usize), usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (*position, index))
+            .collect();
+
+        for (index, target) in targets.iter().enumerate() {
+            let Some(&other) = target.and_then(|target| by_position.get(&target)) else {
+                continue;
+            };
+            if other != index && targets[other] == Some(positions[index]) {
+                moving[index] = false;
+                moving[other] = false;
+            }
+        }
+
+        let mut contenders: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for (index, target) in targets.iter().enumerate() {
+            if let (Some(target), true) = (target, moving[index]) {
+                contenders.entry(*target).or_default().push(index);
+            }
+        }
+        for indexes in contenders.values().filter(|indexes| indexes.len() > 1) {
+            let winner = match policy {
+                ConflictPolicy::BothBlocked => None,
+                ConflictPolicy::PriorityByName => indexes
+                    .iter()
+                    .min_by_key(|index| &self.adventurers[**index].name)
+                    .copied(),
+                ConflictPolicy::Random(_) => Some(*self.rng.choose(indexes)),
+            };
+            for index in indexes.iter() {
+                moving[*index] = Some(*index) == winner;
+            }
+        }
+
+        // Each cell has at most one mover left: blocking it may in turn block
+        // the mover into its own cell
+        let mover_into: HashMap<(usize, usize), usize> = targets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, target)| Some((target.filter(|_| moving[index])?, index)))
+            .collect();
+        let mut stopped: Vec<usize> = (0..positions.len())
+            .filter(|index| !moving[*index])
+            .collect();
+        while let Some(index) = stopped.pop() {
+            if let Some(&mover) = mover_into.get(&positions[index]) {
+                if moving[mover] {
+                    moving[mover] = false;
+                    stopped.push(mover);
+                }
+            }
+        }
+
+        moving
+    }
+
+    /// Plays turns until every pattern is played, the turn limit is reached
+    /// or, with looping patterns or scripts, the simulation comes back to an
+    /// earlier state. Finite patterns always end, so only runs that can go
+    /// on forever are checked for cycles. Without a turn limit, runs with
+    /// other controllers than patterns stop after `DEFAULT_MAX_TURNS`.
+    pub fn run(&mut self) -> RunOutcome {
+        let max_turns = self
+            .max_turns
+            .or_else(|| self.has_controllers().then_some(DEFAULT_MAX_TURNS));
+        let start = self.can_cycle().then(|| self.clone());
+        // Brent's algorithm: the state compared against is moved forward
+        // each time the distance to it reaches a power of two
+        let mut compared = self.state();
+        let (mut power, mut period) = (1, 0);
+
+        loop {
+            if max_turns.is_some_and(|max_turns| self.turn >= max_turns) {
+                return RunOutcome::TurnLimitReached;
+            }
+            if !self.step() {
+                return RunOutcome::Finished;
+            }
+            let Some(start) = &start else {
+                continue;
+            };
+
+            period += 1;
+            let state = self.state();
+            if state == compared {
+                return RunOutcome::CycleDetected {
+                    period,
+                    first_turn: Self::first_turn_of_cycle(start.clone(), period),
+                };
+            }
+            if period == power {
+                compared = state;
+                power *= 2;
+                period = 0;
+            }
+        }
+    }
+
+    /// First turn whose state comes back `period` turns later, found by
+    /// playing two copies of the starting simulation `period` turns apart.
+    fn first_turn_of_cycle(start: Simulation, period: usize) -> usize {
+        let mut behind = start.clone();
+        let mut ahead = start;
+        for _ in 0..period {
+            ahead.step();
+        }
+
+        while behind.state() != ahead.state() {
+            behind.step();
+            ahead.step();
+        }
+
+        behind.turn
+    }
+
+    fn state(&self) -> State {
+        State {
+            adventurers: self
+                .adventurers
+                .iter()
+                .map(|adventurer| {
+                    (
+                        adventurer.position,
+                        adventurer.orientation,
+                        adventurer.cursor,
+                        adventurer.waiting,
+                        adventurer.treasure,
+                        adventurer.banked,
+                        self.turn % adventurer.speed.turns,
+                    )
+                })
+                .collect(),
+            controllers: self
+                .controllers
+                .iter()
+                .map(|controller| controller.state())
+                .collect(),
+            commands: self.commands.clone(),
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Treasure banked by the team when the map has banks, treasure carried
+    /// otherwise.
+    pub fn score(&self) -> usize {
+        if self.map.has_banks() {
+            self.banked()
+        } else {
+            self.carried()
+        }
+    }
+
+    pub fn carried(&self) -> usize {
+        self.adventurers
+            .iter()
+            .map(|adventurer| adventurer.treasure)
+            .sum()
+    }
+
+    pub fn banked(&self) -> usize {
+        self.adventurers
+            .iter()
+            .map(|adventurer| adventurer.banked)
+            .sum()
+    }
+
+    pub fn to_hashmap(&self) -> HashMap<String, Vec<Element>> {
+        self.map.to_hashmap(self.adventurers.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    /* SHOULD ADVENTURER MOVE */
+
+    use std::collections::{HashMap, HashSet};
+
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::{
+        element::{RawBank, RawMountain, RawPortal, RawTerrain, RawTreasure, Speed},
+        generator::{generate, tests::random_options},
+        grid::Grid,
+        map::Topology,
+        storage::StorageKind,
+    };
+
+    const ROW_SIZE_MAP: usize = 3;
+    const COL_SIZE_MAP: usize = 4;
+
+    fn init_mock_map() -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "M".to_string(),
+            vec![
+                Element::RawMap(RawMap::new(4, 4)),
+                Element::RawAdventurer(RawAdventurer::new(
+                    "Lara".to_string(),
+                    4,
+                    4,
+                    Orientation::South,
+                    "AA".to_string(),
+                )),
+                Element::RawTreasure(RawTreasure::new(0, 1, 2)),
+                Element::RawMountain(RawMountain::new(2, 3)),
+                Element::RawMountain(RawMountain::new(1, 1)),
+            ],
+        );
+
+        Map::new(ROW_SIZE_MAP, COL_SIZE_MAP, hash_map)
+    }
+
+    #[test]
+    fn should_adventurer_move_to_next_position() {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer =
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string());
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 1, 0);
+
+        // Then
+        let expected_adventurer =
+            RawAdventurer::new("Lara".to_string(), 1, 0, Orientation::East, "A".to_string());
+        assert_eq!(expected_adventurer, result_adventurer)
+    }
+
+    #[test]
+    fn should_adventurer_get_treasure_when_move_to_treasure_position() {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
+
+        // Then
+        let mut expected_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            1,
+            Orientation::South,
+            "A".to_string(),
+        );
+        expected_adventurer.update_treasure();
+
+        assert_eq!(expected_adventurer, result_adventurer);
+    }
+
+    /* SHOULD ADVENTURER NOT MOVE */
+
+    #[test]
+    fn should_adventurer_do_nothing_when_moving_to_mountain_position() {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            1,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
+
+        // Then
+        let expected_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            1,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        assert_eq!(expected_adventurer, result_adventurer);
+    }
+
+    #[test]
+    fn should_adventurer_not_move_when_next_position_x_is_oustide_map() {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            2,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 1, 0);
+
+        // Then
+        let expected_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            2,
+            0,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        assert_eq!(expected_adventurer, result_adventurer);
+    }
+
+    #[test]
+    fn should_adventurer_not_move_when_next_position_y_is_oustide_map() {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            3,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 0, 1);
+
+        // Then
+        let expected_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            0,
+            3,
+            Orientation::South,
+            "A".to_string(),
+        );
+
+        assert_eq!(expected_adventurer, result_adventurer);
+    }
+
+    #[test]
+    fn should_adventurer_not_move_when_next_position_is_occupied() {
+        // Given
+        let mut mock_map = init_mock_map();
+        mock_map.occupy((1, 0));
+        let fake_adventurer =
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string());
+
+        // When
+        let result_adventurer = update_position(&mut mock_map, fake_adventurer, 1, 0);
+
+        // Then
+        let expected_adventurer =
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string());
+        assert_eq!(expected_adventurer, result_adventurer);
+    }
+
+    /* SHOULD GET SEQUENCE */
+
+    #[test]
+    fn should_get_adeventurer_action_sequence() {
+        // Given
+        let sequence = "DADG";
+
+        // When
+        let result = get_sequence(sequence);
+
+        // Then
+        let expected = ('D', vec!['A', 'D', 'G']);
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    #[should_panic(expected = "Input pattern is empty")]
+    fn should_panic_when_empty_action_sequence() {
+        // Given
+        let sequence = "";
+
+        // When + Then
+        get_sequence(sequence);
+    }
+
+    /* SHOULD UPDATE ADVENTURER */
+
+    #[parameterized(
+        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East},
+        coordinates = {(0, 0), (1, 2), (0, 0), (0,0)},
+        expected_coordinates = {(0, 0), (1, 3), (0,0), (1,0)})]
+    fn should_adventurer_not_change_orientation_when_move(
+        orientation: Orientation,
+        coordinates: (usize, usize),
+        expected_coordinates: (usize, usize),
+    ) {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            coordinates.0,
+            coordinates.1,
+            orientation,
+            "AG".to_string(),
+        );
+
+        // When
+        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
+
+        // Then
+        let expected_adventurer = RawAdventurer {
+            cursor: 1,
+            ..RawAdventurer::new(
+                "Lara".to_string(),
+                expected_coordinates.0,
+                expected_coordinates.1,
+                orientation,
+                "AG".to_string(),
+            )
+        };
+
+        assert_eq!(expected_adventurer, adventurer_result);
+        assert_eq!(adventurer_result.remaining_pattern(), "G");
+    }
+
+    #[parameterized(
+        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East },
+        expected_orientation = { Orientation::West,  Orientation::East, Orientation::South, Orientation::North},
+        coordinates = {(0, 0), (0, 0), (0, 0), (0, 0)})]
+    fn should_adventurer_change_orientation_when_adventurer_turn_to_left(
+        orientation: Orientation,
+        expected_orientation: Orientation,
+        coordinates: (usize, usize),
+    ) {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            coordinates.0,
+            coordinates.1,
+            orientation,
+            "GA".to_string(),
+        );
+
+        // When
+        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
+
+        // Then
+        let expected_adventurer = RawAdventurer {
+            cursor: 1,
+            ..RawAdventurer::new(
+                "Lara".to_string(),
+                coordinates.0,
+                coordinates.1,
+                expected_orientation,
+                "GA".to_string(),
+            )
+        };
+
+        assert_eq!(expected_adventurer, adventurer_result);
+        assert_eq!(adventurer_result.remaining_pattern(), "A");
+    }
+
+    #[parameterized(
+        orientation = { Orientation::North,  Orientation::South, Orientation::West, Orientation::East },
+        expected_orientation = { Orientation::East,  Orientation::West, Orientation::North, Orientation::South},
+        coordinates = {(0, 0), (0, 0), (0, 0), (0, 0)})]
+    fn should_adventurer_change_orientation_when_adventurer_turn_to_right(
+        orientation: Orientation,
+        expected_orientation: Orientation,
+        coordinates: (usize, usize),
+    ) {
+        // Given
+        let mut mock_map = init_mock_map();
+        let fake_adventurer = RawAdventurer::new(
+            "Lara".to_string(),
+            coordinates.0,
+            coordinates.1,
+            orientation,
+            "DA".to_string(),
+        );
+
+        // When
+        let adventurer_result = update_adventurer(fake_adventurer, &mut mock_map);
+
+        // Then
+        let expected_adventurer = RawAdventurer {
+            cursor: 1,
+            ..RawAdventurer::new(
+                "Lara".to_string(),
+                coordinates.0,
+                coordinates.1,
+                expected_orientation,
+                "DA".to_string(),
+            )
+        };
+
+        assert_eq!(expected_adventurer, adventurer_result);
+        assert_eq!(adventurer_result.remaining_pattern(), "A");
+    }
+
+    /* SHOULD RUN SIMULATION */
+
+    #[test]
+    fn should_earlier_adventurer_win_contested_cell() {
+        // Given
+        let the_map = Map::new(3, 1, HashMap::new());
+        let adventurers = vec![
+            RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+            RawAdventurer::new("Toto".to_string(), 2, 0, Orientation::West, "A".to_string()),
+        ];
+        let mut simulation = Simulation::new(the_map, adventurers);
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.adventurers[0].position, (1, 0));
+        assert_eq!(simulation.adventurers[1].position, (2, 0));
+        assert!(simulation.is_done());
+    }
+
+    #[test]
+    fn should_sum_treasure_of_all_adventurers_as_score() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("C".to_string(), vec![Element::RawMap(RawMap::new(3, 4))]);
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 1, 2))],
+        );
+        hash_map.insert(
+            "A".to_string(),
+            vec![Element::RawAdventurer(RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::South,
+                "AAGGA".to_string(),
+            ))],
+        );
+        let mut simulation = Simulation::from_hashmap(&hash_map);
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.score(), 2);
+    }
+
+    /* SHOULD APPLY TERRAIN */
+
+    fn init_terrain_corridor(terrain: Vec<((usize, usize), Terrain)>) -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        for (position, terrain) in terrain {
+            hash_map
+                .entry(terrain.key().unwrap().to_string())
+                .or_default()
+                .push(Element::RawTerrain(RawTerrain::new(
+                    position.0, position.1, terrain,
+                )));
+        }
+
+        Map::new(5, 1, hash_map)
+    }
+
+    fn walk_east(the_map: Map, pattern: &str) -> RawAdventurer {
+        let mut simulation = Simulation::new(
+            the_map,
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                pattern.to_string(),
+            )],
+        );
+        simulation.run();
+
+        simulation.adventurers[0].clone()
+    }
+
+    #[test]
+    fn should_not_enter_water() {
+        let the_map = init_terrain_corridor(vec![((1, 0), Terrain::Water)]);
+
+        assert_eq!(walk_east(the_map, "A").position, (0, 0));
+    }
+
+    #[test]
+    fn should_slide_on_ice_until_blocked() {
+        // Given: the slide over (1, 0) and (2, 0) stops on the plain (3, 0)
+        let the_map = init_terrain_corridor(vec![((1, 0), Terrain::Ice), ((2, 0), Terrain::Ice)]);
+
+        // When + Then
+        assert_eq!(walk_east(the_map, "A").position, (3, 0));
+    }
+
+    #[test]
+    fn should_stop_sliding_on_ice_before_water() {
+        let the_map = init_terrain_corridor(vec![((1, 0), Terrain::Ice), ((2, 0), Terrain::Water)]);
+
+        assert_eq!(walk_east(the_map, "A").position, (1, 0));
+    }
+
+    #[test]
+    fn should_wait_one_turn_after_entering_swamp() {
+        // Given
+        let the_map = init_terrain_corridor(vec![((1, 0), Terrain::Swamp)]);
+        let mut simulation = Simulation::new(
+            the_map,
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                "AA".to_string(),
+            )],
+        );
+
+        // When
+        let positions: Vec<(usize, usize)> = (0..3)
+            .map(|_| {
+                simulation.step();
+                simulation.adventurers[0].position
+            })
+            .collect();
+
+        // Then
+        assert_eq!(positions, vec![(1, 0), (1, 0), (2, 0)]);
+        assert!(simulation.is_done());
+    }
+
+    #[parameterized(
+        conveyor = { Orientation::East, Orientation::South, Orientation::West },
+        expected = { (2, 0), (1, 0), (0, 0) })]
+    fn should_follow_conveyor(conveyor: Orientation, expected: (usize, usize)) {
+        // Given: a south conveyor cannot push out of the one-cell-high map,
+        // a west conveyor cannot be entered going east
+        let the_map = init_terrain_corridor(vec![((1, 0), Terrain::Conveyor(conveyor))]);
+
+        // When + Then
+        assert_eq!(walk_east(the_map, "A").position, expected);
+    }
+
+    /* SHOULD WRAP AROUND */
+
+    #[test]
+    fn should_walk_off_east_edge_into_west_edge_on_torus() {
+        // Given
+        let mut the_map = Map::new(3, 1, HashMap::new());
+        the_map.topology = Topology::Toroidal;
+        let mut simulation = Simulation::new(
+            the_map,
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                2,
+                0,
+                Orientation::East,
+                "AA".to_string(),
+            )],
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn should_stop_sliding_around_an_icy_torus() {
+        // Given
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "I".to_string(),
+            (0..3)
+                .map(|x| Element::RawTerrain(RawTerrain::new(x, 0, Terrain::Ice)))
+                .collect(),
+        );
+        let mut the_map = Map::new(3, 1, hash_map);
+        the_map.topology = Topology::Toroidal;
+        let mut simulation = Simulation::new(
+            the_map,
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                "A".to_string(),
+            )],
+        );
+
+        // When + Then
+        simulation.run();
+        assert!(simulation.is_done());
+    }
+
+    /* SHOULD PLAY ON HEX GRID */
+
+    #[test]
+    fn should_turn_by_sixty_degrees_and_advance_on_hex_grid() {
+        // Given
+        let mut the_map = Map::new(3, 3, HashMap::new());
+        the_map.grid = Grid::Hex;
+        let mut simulation = Simulation::new(
+            the_map,
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                2,
+                Orientation::North,
+                "DADA".to_string(),
+            )],
+        );
+
+        // When
+        simulation.run();
+
+        // Then: north-east then south-east
+        assert_eq!(positions(&simulation), vec![(2, 1)]);
+        assert_eq!(
+            simulation.adventurers[0].orientation,
+            Orientation::SouthEast
+        );
+    }
+
+    /* SHOULD TAKE PORTALS */
+
+    fn init_portal_map() -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(1, 0, 3, 2))],
+        );
+
+        Map::new(5, 3, hash_map)
+    }
+
+    #[test]
+    fn should_come_out_of_other_portal_keeping_orientation() {
+        // Given
+        let mut simulation = Simulation::new(
+            init_portal_map(),
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                "AA".to_string(),
+            )],
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(4, 2)]);
+        assert_eq!(simulation.adventurers[0].orientation, Orientation::East);
+    }
+
+    #[test]
+    fn should_stay_on_portal_when_other_end_is_occupied() {
+        // Given
+        let mut simulation = Simulation::new(
+            init_portal_map(),
+            vec![
+                RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new("Toto".to_string(), 3, 2, Orientation::North, "".to_string()),
+            ],
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(1, 0), (3, 2)]);
+    }
+
+    /* SHOULD BANK TREASURE */
+
+    // B T T . .
+    fn init_bank_map() -> Map {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("B".to_string(), vec![Element::RawBank(RawBank::new(0, 0))]);
+        hash_map.insert(
+            "T".to_string(),
+            vec![
+                Element::RawTreasure(RawTreasure::new(1, 0, 3)),
+                Element::RawTreasure(RawTreasure::new(2, 0, 3)),
+            ],
+        );
+
+        Map::new(5, 1, hash_map)
+    }
+
+    #[test]
+    fn should_not_collect_beyond_capacity() {
+        // Given
+        let mut simulation = Simulation::new(
+            init_bank_map(),
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                1,
+                0,
+                Orientation::East,
+                "AGGA".to_string(),
+            )
+            .with_capacity(Some(1))],
+        );
+
+        // When
+        simulation.run();
+
+        // Then: the second pile is left untouched
+        assert_eq!(simulation.adventurers[0].treasure, 1);
+        assert_eq!(
+            simulation.map.element_at((1, 0)),
+            Some(&Element::RawTreasure(RawTreasure::new(1, 0, 3)))
+        );
+    }
+
+    #[test]
+    fn should_score_banked_treasure_when_map_has_banks() {
+        // Given
+        let mut simulation = Simulation::new(
+            init_bank_map(),
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                3,
+                0,
+                Orientation::West,
+                "AAAGGA".to_string(),
+            )
+            .with_capacity(Some(2))],
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.adventurers[0].position, (1, 0));
+        assert_eq!((simulation.carried(), simulation.banked()), (1, 2));
+        assert_eq!(simulation.score(), 2);
+    }
+
+    /* SHOULD RESOLVE SIMULTANEOUS MOVES */
+
+    fn init_corridor(adventurers: Vec<RawAdventurer>, policy: ConflictPolicy) -> Simulation {
+        Simulation::new(Map::new(3, 1, HashMap::new()), adventurers)
+            .with_resolution(ResolutionMode::Simultaneous(policy))
+    }
+
+    fn positions(simulation: &Simulation) -> Vec<(usize, usize)> {
+        simulation
+            .adventurers
+            .iter()
+            .map(|adventurer| adventurer.position)
+            .collect()
+    }
+
+    #[parameterized(
+        policy = { ConflictPolicy::BothBlocked, ConflictPolicy::PriorityByName },
+        expected = { vec![(0, 0), (2, 0)], vec![(0, 0), (1, 0)] })]
+    fn should_resolve_contested_cell_with_policy(
+        policy: ConflictPolicy,
+        expected: Vec<(usize, usize)>,
+    ) {
+        // Given
+        let mut simulation = init_corridor(
+            vec![
+                RawAdventurer::new("Toto".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new("Lara".to_string(), 2, 0, Orientation::West, "A".to_string()),
+            ],
+            policy,
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), expected);
+    }
+
+    #[test]
+    fn should_let_exactly_one_adventurer_win_with_random_policy() {
+        for seed in 0..10 {
+            // Given
+            let mut simulation = init_corridor(
+                vec![
+                    RawAdventurer::new(
+                        "Toto".to_string(),
+                        0,
+                        0,
+                        Orientation::East,
+                        "A".to_string(),
+                    ),
+                    RawAdventurer::new(
+                        "Lara".to_string(),
+                        2,
+                        0,
+                        Orientation::West,
+                        "A".to_string(),
+                    ),
+                ],
+                ConflictPolicy::Random(seed),
+            );
+
+            // When
+            simulation.run();
+
+            // Then
+            assert!(positions(&simulation).contains(&(1, 0)));
+        }
+    }
+
+    #[test]
+    fn should_block_adventurers_swapping_places() {
+        // Given
+        let mut simulation = init_corridor(
+            vec![
+                RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new("Toto".to_string(), 1, 0, Orientation::West, "A".to_string()),
+            ],
+            ConflictPolicy::PriorityByName,
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn should_follow_adventurer_leaving_its_cell() {
+        // Given: in sequential mode Lara would be blocked by Toto
+        let mut simulation = init_corridor(
+            vec![
+                RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new("Toto".to_string(), 1, 0, Orientation::East, "A".to_string()),
+            ],
+            ConflictPolicy::BothBlocked,
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn should_block_adventurer_moving_into_one_that_stays() {
+        // Given
+        let mut simulation = init_corridor(
+            vec![
+                RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new(
+                    "Toto".to_string(),
+                    1,
+                    0,
+                    Orientation::North,
+                    "G".to_string(),
+                ),
+            ],
+            ConflictPolicy::BothBlocked,
+        );
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(0, 0), (1, 0)]);
+        assert_eq!(simulation.adventurers[1].orientation, Orientation::West);
+    }
+
+    /// Cells marked occupied on the map, which must be those of the
+    /// adventurers.
+    fn occupied_cells(simulation: &Simulation) -> Vec<(usize, usize)> {
+        (0..simulation.map.rows)
+            .flat_map(|x| (0..simulation.map.cols).map(move |y| (x, y)))
+            .filter(|position| simulation.map.is_occupied(*position))
+            .collect()
+    }
+
+    #[test]
+    fn should_keep_cell_entered_by_follower_occupied() {
+        // Given: Lara follows Toto, then Zed slides on the ice towards her
+        let mut simulation = Simulation::from_hashmap(
+            &crate::file::read_scenario(
+                "C - 4 - 4\nI - 2 - 0\nA - Lara - 0 - 0 - E - A\n\
+                 A - Toto - 1 - 0 - S - A\nA - Zed - 3 - 0 - N - GA\n"
+                    .as_bytes(),
+            )
+            .unwrap(),
+        )
+        .with_resolution(ResolutionMode::Simultaneous(ConflictPolicy::BothBlocked));
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(1, 0), (1, 1), (2, 0)]);
+        let mut expected = positions(&simulation);
+        expected.sort();
+        assert_eq!(occupied_cells(&simulation), expected);
+    }
+
+    #[test]
+    fn should_stop_slide_before_cell_entered_on_same_turn() {
+        // Given: Lara slides on the ice towards the cell Toto enters
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "I".to_string(),
+            vec![
+                Element::RawTerrain(RawTerrain::new(1, 0, Terrain::Ice)),
+                Element::RawTerrain(RawTerrain::new(2, 0, Terrain::Ice)),
+            ],
+        );
+        let mut simulation = Simulation::new(
+            Map::new(5, 2, hash_map),
+            vec![
+                RawAdventurer::new("Lara".to_string(), 0, 0, Orientation::East, "A".to_string()),
+                RawAdventurer::new(
+                    "Toto".to_string(),
+                    3,
+                    1,
+                    Orientation::North,
+                    "A".to_string(),
+                ),
+            ],
+        )
+        .with_resolution(ResolutionMode::Simultaneous(ConflictPolicy::BothBlocked));
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(2, 0), (3, 0)]);
+        assert_eq!(occupied_cells(&simulation), vec![(2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn should_parse_conflict_policy() {
+        assert_eq!("blocked".parse(), Ok(ConflictPolicy::BothBlocked));
+        assert_eq!("name".parse(), Ok(ConflictPolicy::PriorityByName));
+        assert_eq!("random:7".parse(), Ok(ConflictPolicy::Random(7)));
+        assert!("first".parse::<ConflictPolicy>().is_err());
+    }
+
+    /* SHOULD STOP RUN */
+
+    fn init_single_adventurer(pattern: &str) -> Simulation {
+        Simulation::new(
+            Map::new(3, 3, HashMap::new()),
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                1,
+                1,
+                Orientation::North,
+                pattern.to_string(),
+            )],
+        )
+    }
+
+    #[test]
+    fn should_finish_when_every_pattern_is_played() {
+        // Given
+        let mut simulation = init_single_adventurer("AD");
+
+        // When + Then
+        assert_eq!(simulation.run(), RunOutcome::Finished);
+        assert_eq!(simulation.turn, 2);
+    }
+
+    #[test]
+    fn should_stop_at_turn_limit() {
+        // Given
+        let mut simulation = init_single_adventurer("DDDDDD").with_max_turns(Some(4));
+
+        // When + Then
+        assert_eq!(simulation.run(), RunOutcome::TurnLimitReached);
+        assert_eq!(simulation.turn, 4);
+        assert_eq!(simulation.adventurers[0].remaining_pattern(), "DD");
+    }
+
+    #[parameterized(
+        pattern = { "D", "AADD", "DA" },
+        expected = {
+            RunOutcome::CycleDetected { period: 4, first_turn: 1 },
+            RunOutcome::CycleDetected { period: 8, first_turn: 2 },
+            RunOutcome::CycleDetected { period: 8, first_turn: 1 },
+        })]
+    fn should_detect_cycle_of_looping_pattern(pattern: &str, expected: RunOutcome) {
+        // Given
+        let mut simulation = init_single_adventurer(pattern).with_looping(true);
+
+        // When + Then
+        assert_eq!(simulation.run(), expected);
+    }
+
+    #[test]
+    fn should_loop_pattern_until_turn_limit_when_state_keeps_changing() {
+        // Given: every advance collects one more treasure
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(1, 0, 100))],
+        );
+        let mut simulation = Simulation::new(
+            Map::new(3, 3, hash_map),
+            vec![RawAdventurer::new(
+                "Lara".to_string(),
+                1,
+                1,
+                Orientation::North,
+                "ADDAGG".to_string(),
+            )],
+        )
+        .with_looping(true)
+        .with_max_turns(Some(60));
+
+        // When + Then
+        assert_eq!(simulation.run(), RunOutcome::TurnLimitReached);
+        assert_eq!(simulation.adventurers[0].treasure, 10);
+        assert_eq!(simulation.stats[0].idle_turns, 0);
+    }
+
+    /* SHOULD PLAY SCRIPTS */
+
+    fn scripted(name: &str, x: usize, orientation: Orientation, source: &str) -> RawAdventurer {
+        RawAdventurer::new(name.to_string(), x, 0, orientation, String::new())
+            .with_controller(ControllerKind::Script(source.parse().unwrap()))
+    }
+
+    #[test]
+    fn should_walk_around_map_until_cycle_with_script() {
+        // Given: forward until blocked, then turn right
+        let mut simulation = Simulation::new(
+            Map::new(3, 3, HashMap::new()),
+            vec![scripted("Lara", 1, Orientation::South, "ahead=blocked?D;A")],
+        );
+
+        // When
+        let outcome = simulation.run();
+
+        // Then
+        assert_eq!(
+            outcome,
+            RunOutcome::CycleDetected {
+                period: 12,
+                first_turn: 3
+            }
+        );
+        assert!(!simulation.is_done());
+    }
+
+    #[parameterized(
+        resolution = {
+            ResolutionMode::Sequential,
+            ResolutionMode::Simultaneous(ConflictPolicy::BothBlocked)
+        },
+        expected = { vec![(2, 0), (1, 0)], vec![(2, 0), (0, 0)] })]
+    fn should_choose_scripted_move_from_state_of_resolution(
+        resolution: ResolutionMode,
+        expected: Vec<(usize, usize)>,
+    ) {
+        // Given: the first adventurer frees the cell the second one faces
+        let mut simulation = Simulation::new(
+            Map::new(3, 1, HashMap::new()),
+            vec![
+                scripted("Lara", 1, Orientation::East, "ahead=free?A;W"),
+                scripted("Toto", 0, Orientation::East, "ahead=free?A;W"),
+            ],
+        )
+        .with_resolution(resolution);
+
+        // When
+        simulation.step();
+
+        // Then
+        assert_eq!(positions(&simulation), expected);
+    }
+
+    #[parameterized(radius = { 0, 1 }, expected = { (0, 0), (2, 0) })]
+    fn should_choose_scripted_move_from_what_adventurer_sees(
+        radius: usize,
+        expected: (usize, usize),
+    ) {
+        // Given: an adventurer seeing nothing around it takes it as blocked
+        let mut simulation = Simulation::new(
+            Map::new(3, 1, HashMap::new()),
+            vec![scripted("Lara", 0, Orientation::East, "ahead=free?A;W")],
+        )
+        .with_sight(Sight {
+            radius,
+            line_of_sight: false,
+        })
+        .with_max_turns(Some(2));
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.adventurers[0].position, expected);
+    }
+
+    /// Map of 5 x 5 with a treasure in every corner and Lara in the middle.
+    fn init_mock_corners(controller: ControllerKind) -> Simulation {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert(
+            "T".to_string(),
+            [(0, 0), (4, 0), (0, 4), (4, 4)]
+                .iter()
+                .map(|(x, y)| Element::RawTreasure(RawTreasure::new(*x, *y, 1)))
+                .collect(),
+        );
+
+        Simulation::new(
+            Map::new(5, 5, hash_map),
+            vec![
+                RawAdventurer::new("Lara".to_string(), 2, 2, Orientation::North, String::new())
+                    .with_controller(controller),
+            ],
+        )
+        .with_max_turns(Some(40))
+    }
+
+    #[test]
+    fn should_collect_every_treasure_with_greedy_controller() {
+        // Given
+        let mut simulation = init_mock_corners(ControllerKind::Greedy);
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.score(), 4);
+    }
+
+    #[test]
+    fn should_stop_random_walk_at_default_turn_limit() {
+        // Given
+        let mut simulation = init_mock_corners(ControllerKind::RandomWalk(5)).with_max_turns(None);
+
+        // When
+        let outcome = simulation.run();
+
+        // Then
+        assert_eq!(outcome, RunOutcome::TurnLimitReached);
+        assert_eq!(simulation.turn, DEFAULT_MAX_TURNS);
+    }
+
+    #[test]
+    fn should_replay_random_walk_with_same_seed() {
+        // Given
+        let mut first = init_mock_corners(ControllerKind::RandomWalk(5));
+        let mut second = init_mock_corners(ControllerKind::RandomWalk(5));
+
+        // When
+        let outcome = first.run();
+        second.run();
+
+        // Then
+        assert_eq!(outcome, RunOutcome::TurnLimitReached);
+        assert_eq!(first.adventurers, second.adventurers);
+    }
+
+    #[test]
+    fn should_detect_cycle_of_deterministic_controller_but_not_of_random_walk() {
+        for (controller, expected) in [
+            (ControllerKind::Greedy, true),
+            (ControllerKind::RandomWalk(1), false),
+        ] {
+            // Given
+            let mut simulation = Simulation::new(
+                Map::new(3, 3, HashMap::new()),
+                vec![RawAdventurer::new(
+                    "Lara".to_string(),
+                    1,
+                    1,
+                    Orientation::North,
+                    String::new(),
+                )
+                .with_controller(controller)],
+            )
+            .with_max_turns(Some(100));
+
+            // When
+            let outcome = simulation.run();
+
+            // Then
+            assert_eq!(
+                matches!(outcome, RunOutcome::CycleDetected { .. }),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn should_wait_when_script_budget_runs_out() {
+        // Given
+        let mut simulation = Simulation::new(
+            Map::new(3, 1, HashMap::new()),
+            vec![scripted("Lara", 0, Orientation::East, "ahead=blocked?D;A")],
+        )
+        .with_script_budget(2)
+        .with_max_turns(Some(3));
+
+        // When
+        simulation.run();
+
+        // Then
+        assert_eq!(simulation.adventurers[0].position, (0, 0));
+        assert_eq!(simulation.adventurers[0].orientation, Orientation::East);
+    }
+
+    /* SHOULD SCHEDULE BY SPEED */
+
+    #[test]
+    fn should_play_adventurers_at_their_speed() {
+        // Given
+        let mut simulation = Simulation::new(
+            Map::new(1, 5, HashMap::new()),
+            vec![
+                RawAdventurer::new(
+                    "Lara".to_string(),
+                    0,
+                    0,
+                    Orientation::South,
+                    "AAAA".to_string(),
+                )
+                .with_speed(Speed::new(2, 1)),
+                RawAdventurer::new(
+                    "Toto".to_string(),
+                    0,
+                    4,
+                    Orientation::North,
+                    "AAAA".to_string(),
+                )
+                .with_speed(Speed::new(1, 2)),
+            ],
+        );
+
+        // When
+        simulation.step();
+        simulation.step();
+
+        // Then: Lara played 4 instructions, Toto only 1
+        assert_eq!(positions(&simulation), vec![(0, 2), (0, 3)]);
+        assert_eq!(simulation.adventurers[1].remaining_pattern(), "AAA");
+    }
+
+    #[test]
+    fn should_interleave_instructions_of_fast_adventurers() {
+        // Given: Toto leaves the cell Lara advances into on her second move
+        let mut simulation = Simulation::new(
+            Map::new(1, 4, HashMap::new()),
+            vec![
+                RawAdventurer::new(
+                    "Lara".to_string(),
+                    0,
+                    0,
+                    Orientation::South,
+                    "AA".to_string(),
+                )
+                .with_speed(Speed::new(2, 1)),
+                RawAdventurer::new(
+                    "Toto".to_string(),
+                    0,
+                    2,
+                    Orientation::South,
+                    "AA".to_string(),
+                )
+                .with_speed(Speed::new(2, 1)),
+            ],
+        );
+
+        // When
+        simulation.step();
+
+        // Then
+        assert_eq!(positions(&simulation), vec![(0, 2), (0, 3)]);
+    }
+
+    /* PROPERTIES */
+
+    fn treasure_on_map(the_map: &Map) -> usize {
+        the_map
+            .elements()
+            .map(|element| match element {
+                Element::RawTreasure(treasure) => treasure.nb_treasure,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn should_play_the_same_with_dense_and_sparse_storage() {
+        for seed in 0..200 {
+            // Given
+            let scenario = generate(&random_options(seed)).unwrap();
+            let simulations = [StorageKind::Dense, StorageKind::Sparse].map(|kind| {
+                let Simulation {
+                    map, adventurers, ..
+                } = Simulation::from_hashmap(&scenario);
+                let content = map.to_hashmap(vec![]);
+                Simulation::new(
+                    Map::with_storage(map.rows, map.cols, content, kind),
+                    adventurers,
+                )
+            });
+
+            // When
+            let [dense, sparse] = simulations.map(|mut simulation| {
+                simulation.run();
+                simulation.to_hashmap()
+            });
+
+            // Then
+            assert_eq!(dense, sparse);
+        }
+    }
+
+    #[test]
+    fn should_conserve_treasure_and_keep_adventurers_on_free_cells() {
+        for seed in 0..200 {
+            // Given
+            let scenario = generate(&random_options(seed)).unwrap();
+            let mut simulation = Simulation::from_hashmap(&scenario);
+            let total_treasure = treasure_on_map(&simulation.map);
+
+            // When + Then
+            while simulation.step() {
+                assert_eq!(
+                    treasure_on_map(&simulation.map) + simulation.score(),
+                    total_treasure
+                );
+
+                let mut positions = HashSet::new();
+                for adventurer in simulation.adventurers.iter() {
+                    let (x, y) = adventurer.position;
+                    assert!(x < simulation.map.rows && y < simulation.map.cols);
+                    assert!(!matches!(
+                        simulation.map.element_at((x, y)),
+                        Some(Element::RawMountain(_))
+                    ));
+                    assert!(positions.insert(adventurer.position));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::element::{Element, Terrain};
+
+/// Maps up to this many cells are always stored densely.
+const DENSE_MAX_CELLS: usize = 64 * 64;
+/// Larger maps are stored sparsely when less than one cell in this many
+/// holds an element or a terrain other than plain.
+const SPARSE_DENSITY: usize = 16;
+
+/// Where a map keeps the element and the terrain of its cells. Positions are
+/// expected on the map, which checks them.
+pub trait CellStore {
+    /// Element of the cell, `None` when it is empty.
+    fn element(&self, position: (usize, usize)) -> Option<&Element>;
+
+    fn element_mut(&mut self, position: (usize, usize)) -> Option<&mut Element>;
+
+    fn set_element(&mut self, position: (usize, usize), element: Element);
+
+    /// Elements of the non-empty cells, `x` by `x`.
+    fn elements(&self) -> impl Iterator<Item = &Element>;
+
+    fn terrain(&self, position: (usize, usize)) -> Terrain;
+
+    fn set_terrain(&mut self, position: (usize, usize), terrain: Terrain);
+
+    /// Cells whose terrain is not plain, `x` by `x`.
+    fn terrains(&self) -> impl Iterator<Item = ((usize, usize), Terrain)> + '_;
+}
+
+/// One slot per cell, the one of `(x, y)` at `x * cols + y`: the fastest
+/// lookups, at the cost of memory proportional to the map area.
+#[derive(Clone)]
+pub struct DenseStore {
+    cols: usize,
+    elements: Vec<Option<Element>>,
+    terrain: Vec<Terrain>,
+}
+
+impl DenseStore {
+    pub fn new(rows: usize, cols: usize) -> DenseStore {
+        DenseStore {
+            cols,
+            elements: vec![None; rows * cols],
+            terrain: vec![Terrain::Plain; rows * cols],
+        }
+    }
+
+    fn index(&self, position: (usize, usize)) -> usize {
+        position.0 * self.cols + position.1
+    }
+}
+
+impl CellStore for DenseStore {
+    fn element(&self, position: (usize, usize)) -> Option<&Element> {
+        self.elements[self.index(position)].as_ref()
+    }
+
+    fn element_mut(&mut self, position: (usize, usize)) -> Option<&mut Element> {
+        let index = self.index(position);
+        self.elements[index].as_mut()
+    }
+
+    fn set_element(&mut self, position: (usize, usize), element: Element) {
+        let index = self.index(position);
+        self.elements[index] = Some(element);
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.elements.iter().flatten()
+    }
+
+    fn terrain(&self, position: (usize, usize)) -> Terrain {
+        self.terrain[self.index(position)]
+    }
+
+    fn set_terrain(&mut self, position: (usize, usize), terrain: Terrain) {
+        let index = self.index(position);
+        self.terrain[index] = terrain;
+    }
+
+    fn terrains(&self) -> impl Iterator<Item = ((usize, usize), Terrain)> + '_ {
+        self.terrain
+            .iter()
+            .enumerate()
+            .filter(|(_, terrain)| **terrain != Terrain::Plain)
+            .map(|(index, terrain)| ((index / self.cols, index % self.cols), *terrain))
+    }
+}
+
+/// Only the non-empty cells, sorted by position: memory proportional to the
+/// number of elements whatever the map area.
+#[derive(Clone, Default)]
+pub struct SparseStore {
+    elements: BTreeMap<(usize, usize), Element>,
+    terrain: BTreeMap<(usize, usize), Terrain>,
+}
+
+impl CellStore for SparseStore {
+    fn element(&self, position: (usize, usize)) -> Option<&Element> {
+        self.elements.get(&position)
+    }
+
+    fn element_mut(&mut self, position: (usize, usize)) -> Option<&mut Element> {
+        self.elements.get_mut(&position)
+    }
+
+    fn set_element(&mut self, position: (usize, usize), element: Element) {
+        self.elements.insert(position, element);
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.elements.values()
+    }
+
+    fn terrain(&self, position: (usize, usize)) -> Terrain {
+        self.terrain
+            .get(&position)
+            .copied()
+            .unwrap_or(Terrain::Plain)
+    }
+
+    fn set_terrain(&mut self, position: (usize, usize), terrain: Terrain) {
+        if terrain == Terrain::Plain {
+            self.terrain.remove(&position);
+        } else {
+            self.terrain.insert(position, terrain);
+        }
+    }
+
+    fn terrains(&self) -> impl Iterator<Item = ((usize, usize), Terrain)> + '_ {
+        self.terrain
+            .iter()
+            .map(|(position, terrain)| (*position, *terrain))
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StorageKind {
+    Dense,
+    Sparse,
+}
+
+impl StorageKind {
+    /// Dense storage for small maps and maps where many cells are filled,
+    /// sparse storage for large mostly-empty ones.
+    pub fn for_density(rows: usize, cols: usize, filled_cells: usize) -> StorageKind {
+        let cells = rows * cols;
+        if cells <= DENSE_MAX_CELLS || filled_cells * SPARSE_DENSITY >= cells {
+            StorageKind::Dense
+        } else {
+            StorageKind::Sparse
+        }
+    }
+
+    /// Kind fitting the scenario content of a map of the given size.
+    pub fn for_content(
+        rows: usize,
+        cols: usize,
+        content: &HashMap<String, Vec<Element>>,
+    ) -> StorageKind {
+        let filled_cells = content
+            .values()
+            .flatten()
+            .map(|element| match element {
+                Element::RawPortal(_) => 2,
+                Element::RawMountain(_)
+                | Element::RawTreasure(_)
+                | Element::RawBank(_)
+                | Element::RawTerrain(_) => 1,
+                _ => 0,
+            })
+            .sum();
+
+        StorageKind::for_density(rows, cols, filled_cells)
+    }
+}
+
+/// Store of a map, dense or sparse.
+#[derive(Clone)]
+pub enum Storage {
+    Dense(DenseStore),
+    Sparse(SparseStore),
+}
+
+impl Storage {
+    pub fn new(rows: usize, cols: usize, kind: StorageKind) -> Storage {
+        match kind {
+            StorageKind::Dense => Storage::Dense(DenseStore::new(rows, cols)),
+            StorageKind::Sparse => Storage::Sparse(SparseStore::default()),
+        }
+    }
+
+    pub fn kind(&self) -> StorageKind {
+        match self {
+            Storage::Dense(_) => StorageKind::Dense,
+            Storage::Sparse(_) => StorageKind::Sparse,
+        }
+    }
+}
+
+/// Dispatches to the store of the map with a match, so that no call goes
+/// through a trait object.
+impl CellStore for Storage {
+    fn element(&self, position: (usize, usize)) -> Option<&Element> {
+        match self {
+            Storage::Dense(store) => store.element(position),
+            Storage::Sparse(store) => store.element(position),
+        }
+    }
+
+    fn element_mut(&mut self, position: (usize, usize)) -> Option<&mut Element> {
+        match self {
+            Storage::Dense(store) => store.element_mut(position),
+            Storage::Sparse(store) => store.element_mut(position),
+        }
+    }
+
+    fn set_element(&mut self, position: (usize, usize), element: Element) {
+        match self {
+            Storage::Dense(store) => store.set_element(position, element),
+            Storage::Sparse(store) => store.set_element(position, element),
+        }
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        match self {
+            Storage::Dense(store) => Cells::Dense(store.elements()),
+            Storage::Sparse(store) => Cells::Sparse(store.elements()),
+        }
+    }
+
+    fn terrain(&self, position: (usize, usize)) -> Terrain {
+        match self {
+            Storage::Dense(store) => store.terrain(position),
+            Storage::Sparse(store) => store.terrain(position),
+        }
+    }
+
+    fn set_terrain(&mut self, position: (usize, usize), terrain: Terrain) {
+        match self {
+            Storage::Dense(store) => store.set_terrain(position, terrain),
+            Storage::Sparse(store) => store.set_terrain(position, terrain),
+        }
+    }
+
+    fn terrains(&self) -> impl Iterator<Item = ((usize, usize), Terrain)> + '_ {
+        match self {
+            Storage::Dense(store) => Cells::Dense(store.terrains()),
+            Storage::Sparse(store) => Cells::Sparse(store.terrains()),
+        }
+    }
+}
+
+/// Iterator over the cells of either store, without boxing it.
+enum Cells<D, S> {
+    Dense(D),
+    Sparse(S),
+}
+
+impl<T, D: Iterator<Item = T>, S: Iterator<Item = T>> Iterator for Cells<D, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Cells::Dense(cells) => cells.next(),
+            Cells::Sparse(cells) => cells.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parameterized::parameterized;
+
+    use super::*;
+    use crate::element::{RawMountain, RawTreasure};
+
+    #[parameterized(kind = { StorageKind::Dense, StorageKind::Sparse })]
+    fn should_store_only_filled_cells_in_position_order(kind: StorageKind) {
+        // Given
+        let mut storage = Storage::new(3, 4, kind);
+
+        // When
+        storage.set_element((2, 3), Element::RawMountain(RawMountain::new(2, 3)));
+        storage.set_element((0, 1), Element::RawTreasure(RawTreasure::new(0, 1, 2)));
+        storage.set_terrain((1, 0), Terrain::Ice);
+        storage.set_terrain((0, 2), Terrain::Water);
+        if let Some(Element::RawTreasure(treasure)) = storage.element_mut((0, 1)) {
+            treasure.nb_treasure -= 1;
+        }
+
+        // Then
+        assert_eq!(
+            storage.elements().cloned().collect::<Vec<_>>(),
+            vec![
+                Element::RawTreasure(RawTreasure::new(0, 1, 1)),
+                Element::RawMountain(RawMountain::new(2, 3)),
+            ]
+        );
+        assert_eq!(storage.element((1, 1)), None);
+        assert_eq!(storage.terrain((1, 0)), Terrain::Ice);
+        assert_eq!(storage.terrain((2, 2)), Terrain::Plain);
+        assert_eq!(
+            storage.terrains().collect::<Vec<_>>(),
+            vec![((0, 2), Terrain::Water), ((1, 0), Terrain::Ice)]
+        );
+    }
+
+    #[parameterized(
+        size = { (3, 4), (100, 100), (100, 100), (10_000, 10_000) },
+        filled_cells = { 0, 625, 624, 10_000 },
+        expected = { StorageKind::Dense, StorageKind::Dense, StorageKind::Sparse, StorageKind::Sparse })]
+    fn should_choose_storage_by_density(
+        size: (usize, usize),
+        filled_cells: usize,
+        expected: StorageKind,
+    ) {
+        assert_eq!(
+            StorageKind::for_density(size.0, size.1, filled_cells),
+            expected
+        );
+    }
+}
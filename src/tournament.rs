@@ -0,0 +1,508 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs::File,
+    io::{self, BufReader, Error, ErrorKind},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use crate::{
+    controller::ControllerKind,
+    element::Element,
+    file::read_scenario,
+    generator::{self, GeneratorError, GeneratorOptions},
+    rng::Rng,
+    simulation::{ResolutionMode, Simulation},
+    validation::{self, ValidationError},
+};
+
+/// Map on which every controller of a tournament plays.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Arena {
+    pub name: String,
+    pub content: HashMap<String, Vec<Element>>,
+}
+
+/// Arenas read from scenario files, named after their path. A file that
+/// cannot be read or holds an invalid scenario fails the whole tournament,
+/// since its games would be missing from every mean.
+pub fn load_arenas(inputs: &[PathBuf]) -> io::Result<Vec<Arena>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let content = read_scenario(BufReader::new(File::open(input)?))?;
+            let errors = validation::validate(&content);
+            if !errors.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "{} : {}",
+                        input.display(),
+                        errors
+                            .iter()
+                            .map(ValidationError::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+
+            Ok(Arena {
+                name: input.display().to_string(),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// `count` arenas generated from `options` with the seeds following
+/// `options.seed`, named after their seed.
+pub fn generate_arenas(
+    options: &GeneratorOptions,
+    count: usize,
+) -> Result<Vec<Arena>, GeneratorError> {
+    (0..count as u64)
+        .map(|offset| {
+            let seed = options.seed.wrapping_add(offset);
+            let content = generator::generate(&GeneratorOptions { seed, ..*options })?;
+
+            Ok(Arena {
+                name: format!("seed {}", seed),
+                content,
+            })
+        })
+        .collect()
+}
+
+pub struct TournamentOptions {
+    pub controllers: Vec<ControllerKind>,
+    /// Games each controller plays on each arena. The seed of a game, from 0,
+    /// changes the seeds of the random walks.
+    pub seeds: u64,
+    /// Turns after which a game stops, since random walks never finish.
+    pub max_turns: usize,
+    pub resolution: ResolutionMode,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            controllers: vec![
+                ControllerKind::Pattern,
+                ControllerKind::RandomWalk(0),
+                ControllerKind::Greedy,
+                ControllerKind::WallFollower { right_hand: true },
+            ],
+            seeds: 10,
+            max_turns: 200,
+            resolution: ResolutionMode::Sequential,
+        }
+    }
+}
+
+/// Scores of one arena and seed, in the order of the controllers.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Game {
+    pub arena: String,
+    pub seed: u64,
+    pub scores: Vec<usize>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ranking {
+    pub rank: usize,
+    pub controller: ControllerKind,
+    pub mean: f64,
+    pub variance: f64,
+    /// Games where no other controller scored more, ties counting for each.
+    pub wins: usize,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TournamentFormat {
+    Csv,
+    Markdown,
+}
+
+impl FromStr for TournamentFormat {
+    type Err = String;
+
+    fn from_str(raw_value: &str) -> Result<Self, Self::Err> {
+        match raw_value {
+            "csv" => Ok(TournamentFormat::Csv),
+            "markdown" => Ok(TournamentFormat::Markdown),
+            _ => Err(format!("Unknown tournament format : {}", raw_value)),
+        }
+    }
+}
+
+/// Controllers compared by the treasure their adventurers score on the same
+/// arenas: every adventurer of an arena is driven by the controller, except
+/// with `pattern`, which keeps the patterns and scripts of the scenario.
+#[derive(PartialEq, Debug)]
+pub struct Tournament {
+    pub games: Vec<Game>,
+    pub ranking: Vec<Ranking>,
+}
+
+impl Tournament {
+    pub fn run(arenas: &[Arena], options: &TournamentOptions) -> Tournament {
+        let games: Vec<Game> = arenas
+            .iter()
+            .flat_map(|arena| (0..options.seeds).map(move |seed| (arena, seed)))
+            .map(|(arena, seed)| Game {
+                arena: arena.name.clone(),
+                seed,
+                scores: options
+                    .controllers
+                    .iter()
+                    .map(|controller| Self::play(arena, controller, seed, options))
+                    .collect(),
+            })
+            .collect();
+
+        let mut ranking: Vec<Ranking> = options
+            .controllers
+            .iter()
+            .enumerate()
+            .map(|(index, controller)| Self::rank(&games, index, controller))
+            .collect();
+        ranking.sort_by(|first, second| {
+            second
+                .mean
+                .total_cmp(&first.mean)
+                .then(second.wins.cmp(&first.wins))
+        });
+
+        for index in 0..ranking.len() {
+            ranking[index].rank = match index {
+                0 => 1,
+                _ if ranking[index].mean == ranking[index - 1].mean
+                    && ranking[index].wins == ranking[index - 1].wins =>
+                {
+                    ranking[index - 1].rank
+                }
+                _ => index + 1,
+            };
+        }
+
+        Tournament { games, ranking }
+    }
+
+    /// Score of the adventurers of `arena` driven by `controller`.
+    fn play(
+        arena: &Arena,
+        controller: &ControllerKind,
+        seed: u64,
+        options: &TournamentOptions,
+    ) -> usize {
+        let mut content = arena.content.clone();
+        if *controller != ControllerKind::Pattern {
+            let mut rng = Rng::new(seed);
+            for element in content.values_mut().flatten() {
+                if let Element::RawAdventurer(adventurer) = element {
+                    adventurer.controller = match controller {
+                        ControllerKind::RandomWalk(base) => {
+                            ControllerKind::RandomWalk(base.wrapping_add(rng.next_u64()))
+                        }
+                        other => other.clone(),
+                    };
+                }
+            }
+        }
+
+        let mut simulation = Simulation::from_hashmap(&content)
+            .with_resolution(options.resolution)
+            .with_max_turns(Some(options.max_turns));
+        simulation.run();
+
+        simulation.score()
+    }
+
+    fn rank(games: &[Game], index: usize, controller: &ControllerKind) -> Ranking {
+        let scores: Vec<f64> = games.iter().map(|game| game.scores[index] as f64).collect();
+        let count = scores.len().max(1) as f64;
+        let mean = scores.iter().sum::<f64>() / count;
+        let variance = scores
+            .iter()
+            .map(|score| (score - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let wins = games
+            .iter()
+            .filter(|game| game.scores.iter().all(|score| *score <= game.scores[index]))
+            .count();
+
+        Ranking {
+            rank: 0,
+            controller: controller.clone(),
+            mean,
+            variance,
+            wins,
+        }
+    }
+
+    pub fn format(&self, format: TournamentFormat) -> String {
+        match format {
+            TournamentFormat::Csv => self.to_csv(),
+            TournamentFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = "rank,controller,mean,variance,wins\n".to_string();
+
+        for ranking in self.ranking.iter() {
+            let _ = writeln!(
+                csv,
+                "{},{},{:.2},{:.2},{}",
+                ranking.rank, ranking.controller, ranking.mean, ranking.variance, ranking.wins
+            );
+        }
+
+        csv
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut table = "| rank | controller | mean | variance | wins |\n\
+                         | ---: | --- | ---: | ---: | ---: |\n"
+            .to_string();
+
+        for ranking in self.ranking.iter() {
+            let _ = writeln!(
+                table,
+                "| {} | {} | {:.2} | {:.2} | {} |",
+                ranking.rank, ranking.controller, ranking.mean, ranking.variance, ranking.wins
+            );
+        }
+
+        table
+    }
+
+    /// One line per game with the score of each controller, in the order
+    /// they were given.
+    pub fn scores_csv(&self, controllers: &[ControllerKind]) -> String {
+        let mut csv = format!(
+            "arena,seed,{}\n",
+            controllers
+                .iter()
+                .map(ControllerKind::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+
+        for game in self.games.iter() {
+            let _ = writeln!(
+                csv,
+                "{},{},{}",
+                game.arena,
+                game.seed,
+                game.scores
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    // L . . T
+    //
+    // Lara's pattern turns her away from the treasure, which the greedy
+    // controller walks to and collects twice by stepping off and back.
+    fn init_mock_arenas() -> Vec<Arena> {
+        vec![Arena {
+            name: "corridor".to_string(),
+            content: read_scenario(
+                "C - 4 - 1\nT - 3 - 0 - 2\nA - Lara - 0 - 0 - E - GAAA\n".as_bytes(),
+            )
+            .unwrap(),
+        }]
+    }
+
+    fn init_mock_options() -> TournamentOptions {
+        TournamentOptions {
+            controllers: vec![ControllerKind::Pattern, ControllerKind::Greedy],
+            seeds: 2,
+            max_turns: 20,
+            ..TournamentOptions::default()
+        }
+    }
+
+    /* SHOULD LOAD ARENAS */
+
+    #[test]
+    fn should_load_arenas_and_reject_invalid_scenario() -> io::Result<()> {
+        // Given
+        let root = TempDir::new()?;
+        let valid = root.path().join("valid.txt");
+        let invalid = root.path().join("invalid.txt");
+        fs::write(&valid, "C - 3 - 4\nA - Lara - 0 - 0 - S - A\n")?;
+        fs::write(&invalid, "C - 3 - 4\nM - 5 - 5\nA - Lara - 0 - 0 - S - A\n")?;
+
+        // When
+        let arenas = load_arenas(&[valid])?;
+        let error = load_arenas(&[invalid]).unwrap_err();
+
+        // Then
+        assert_eq!(arenas.len(), 1);
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_generate_arenas_from_following_seeds() {
+        // Given
+        let options = GeneratorOptions {
+            seed: 4,
+            ..GeneratorOptions::default()
+        };
+
+        // When
+        let arenas = generate_arenas(&options, 2).unwrap();
+
+        // Then
+        assert_eq!(arenas[0].name, "seed 4");
+        assert_eq!(arenas[1].name, "seed 5");
+        assert_eq!(
+            arenas[1].content,
+            generator::generate(&GeneratorOptions {
+                seed: 5,
+                ..GeneratorOptions::default()
+            })
+            .unwrap()
+        );
+    }
+
+    /* SHOULD RUN TOURNAMENT */
+
+    #[test]
+    fn should_score_every_controller_on_every_arena_and_seed() {
+        // When
+        let tournament = Tournament::run(&init_mock_arenas(), &init_mock_options());
+
+        // Then
+        assert_eq!(
+            tournament.games,
+            vec![
+                Game {
+                    arena: "corridor".to_string(),
+                    seed: 0,
+                    scores: vec![0, 2],
+                },
+                Game {
+                    arena: "corridor".to_string(),
+                    seed: 1,
+                    scores: vec![0, 2],
+                },
+            ]
+        );
+        assert_eq!(
+            tournament.ranking[0],
+            Ranking {
+                rank: 1,
+                controller: ControllerKind::Greedy,
+                mean: 2.0,
+                variance: 0.0,
+                wins: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn should_replay_random_walks_with_same_seeds() {
+        // Given
+        let options = TournamentOptions {
+            controllers: vec![ControllerKind::RandomWalk(3)],
+            seeds: 4,
+            ..init_mock_options()
+        };
+
+        // When
+        let first = Tournament::run(&init_mock_arenas(), &options);
+        let second = Tournament::run(&init_mock_arenas(), &options);
+
+        // Then
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn should_share_rank_and_wins_on_tie() {
+        // Given
+        let options = TournamentOptions {
+            controllers: vec![ControllerKind::Greedy, ControllerKind::Greedy],
+            ..init_mock_options()
+        };
+
+        // When
+        let tournament = Tournament::run(&init_mock_arenas(), &options);
+
+        // Then
+        assert_eq!(
+            tournament
+                .ranking
+                .iter()
+                .map(|ranking| (ranking.rank, ranking.wins))
+                .collect::<Vec<_>>(),
+            vec![(1, 2), (1, 2)]
+        );
+    }
+
+    /* SHOULD FORMAT TOURNAMENT */
+
+    #[test]
+    fn should_format_ranking_as_csv_and_markdown() {
+        // Given
+        let tournament = Tournament::run(&init_mock_arenas(), &init_mock_options());
+
+        // When
+        let csv = tournament.format(TournamentFormat::Csv);
+        let markdown = tournament.format(TournamentFormat::Markdown);
+
+        // Then
+        assert_eq!(
+            csv,
+            "rank,controller,mean,variance,wins\n\
+             1,greedy,2.00,0.00,2\n\
+             2,pattern,0.00,0.00,0\n"
+        );
+        assert_eq!(
+            markdown,
+            "| rank | controller | mean | variance | wins |\n\
+             | ---: | --- | ---: | ---: | ---: |\n\
+             | 1 | greedy | 2.00 | 0.00 | 2 |\n\
+             | 2 | pattern | 0.00 | 0.00 | 0 |\n"
+        );
+    }
+
+    #[test]
+    fn should_list_scores_of_every_game() {
+        // Given
+        let options = init_mock_options();
+        let tournament = Tournament::run(&init_mock_arenas(), &options);
+
+        // When
+        let csv = tournament.scores_csv(&options.controllers);
+
+        // Then
+        assert_eq!(
+            csv,
+            "arena,seed,pattern,greedy\ncorridor,0,0,2\ncorridor,1,0,2\n"
+        );
+    }
+}
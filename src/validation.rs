@@ -0,0 +1,273 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::element::{Element, Orientation, RawMap};
+
+#[derive(PartialEq, Debug)]
+pub enum ValidationError {
+    MissingMap,
+    MissingAdventurers,
+    OutOfBounds(String, (usize, usize)),
+    SharedCell(String, (usize, usize)),
+    InvalidOrientation(String, Orientation),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::MissingMap => write!(f, "no map size found"),
+            ValidationError::MissingAdventurers => write!(f, "no adventurers found"),
+            ValidationError::OutOfBounds(key, position) => write!(
+                f,
+                "{} at {} - {} is outside the map",
+                key, position.0, position.1
+            ),
+            ValidationError::SharedCell(key, position) => write!(
+                f,
+                "{} at {} - {} shares its cell with another element",
+                key, position.0, position.1
+            ),
+            ValidationError::InvalidOrientation(name, orientation) => write!(
+                f,
+                "{} faces {} which does not exist on this grid",
+                name,
+                Orientation::from(*orientation)
+            ),
+        }
+    }
+}
+
+/// Checks a parsed scenario before it is turned into a `Map`, which would
+/// panic on most of these errors.
+pub fn validate(content: &HashMap<String, Vec<Element>>) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    let size = content.get("C").and_then(|raw_maps| {
+        raw_maps.iter().find_map(|element| match element {
+            Element::RawMap(RawMap { position }) => Some(*position),
+            _ => None,
+        })
+    });
+    let Some((rows, cols)) = size else {
+        errors.push(ValidationError::MissingMap);
+        return errors;
+    };
+
+    if content
+        .get("A")
+        .is_none_or(|adventurers| adventurers.is_empty())
+    {
+        errors.push(ValidationError::MissingAdventurers);
+    }
+
+    let mut keys: Vec<&String> = content.keys().filter(|key| *key != "C").collect();
+    keys.sort();
+
+    // Terrain lies under the other elements, so it only clashes with terrain
+    let mut used: HashSet<(usize, usize)> = HashSet::new();
+    let mut used_terrain: HashSet<(usize, usize)> = HashSet::new();
+    for key in keys {
+        for element in content[key].iter() {
+            // Both ends of a portal take a cell
+            let positions: Vec<(usize, usize)> = match element {
+                Element::RawPortal(portal) => vec![portal.position, portal.destination],
+                _ => element.position().into_iter().collect(),
+            };
+            let used = match element {
+                Element::RawTerrain(_) => &mut used_terrain,
+                _ => &mut used,
+            };
+
+            for position in positions {
+                if position.0 >= rows || position.1 >= cols {
+                    errors.push(ValidationError::OutOfBounds(key.clone(), position));
+                } else if !used.insert(position) {
+                    errors.push(ValidationError::SharedCell(key.clone(), position));
+                }
+            }
+        }
+    }
+
+    let grid = content
+        .get("Grid")
+        .into_iter()
+        .flatten()
+        .find_map(|element| match element {
+            Element::RawGrid(grid) => Some(*grid),
+            _ => None,
+        })
+        .unwrap_or_default();
+    for element in content.get("A").into_iter().flatten() {
+        if let Element::RawAdventurer(adventurer) = element {
+            if grid.geometry().offset(adventurer.orientation).is_none() {
+                errors.push(ValidationError::InvalidOrientation(
+                    adventurer.name.clone(),
+                    adventurer.orientation,
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::{RawAdventurer, RawMountain, RawPortal, RawTerrain, RawTreasure, Terrain};
+    use crate::grid::Grid;
+
+    use super::*;
+
+    /* MOCK INIT */
+
+    fn init_mock_content() -> HashMap<String, Vec<Element>> {
+        let mut hash_map: HashMap<String, Vec<Element>> = HashMap::new();
+        hash_map.insert("C".to_string(), vec![Element::RawMap(RawMap::new(3, 4))]);
+        hash_map.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(1, 1))],
+        );
+        hash_map.insert(
+            "T".to_string(),
+            vec![Element::RawTreasure(RawTreasure::new(0, 1, 2))],
+        );
+        hash_map.insert(
+            "A".to_string(),
+            vec![Element::RawAdventurer(RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::South,
+                "AA".to_string(),
+            ))],
+        );
+
+        hash_map
+    }
+
+    /* SHOULD VALIDATE */
+
+    #[test]
+    fn should_find_no_error_in_valid_scenario() {
+        assert!(validate(&init_mock_content()).is_empty());
+    }
+
+    #[test]
+    fn should_report_missing_map() {
+        // Given
+        let mut content = init_mock_content();
+        content.remove("C");
+
+        // When + Then
+        assert_eq!(validate(&content), vec![ValidationError::MissingMap]);
+    }
+
+    #[test]
+    fn should_report_missing_adventurers() {
+        // Given
+        let mut content = init_mock_content();
+        content.remove("A");
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::MissingAdventurers]
+        );
+    }
+
+    #[test]
+    fn should_report_element_outside_map() {
+        // Given
+        let mut content = init_mock_content();
+        content.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(5, 5))],
+        );
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::OutOfBounds("M".to_string(), (5, 5))]
+        );
+    }
+
+    #[test]
+    fn should_report_adventurer_standing_on_mountain() {
+        // Given
+        let mut content = init_mock_content();
+        content.insert(
+            "M".to_string(),
+            vec![Element::RawMountain(RawMountain::new(0, 0))],
+        );
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::SharedCell("M".to_string(), (0, 0))]
+        );
+    }
+
+    #[test]
+    fn should_accept_terrain_under_elements_but_not_shared_terrain() {
+        // Given
+        let mut content = init_mock_content();
+        content.insert(
+            "I".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(0, 0, Terrain::Ice))],
+        );
+        content.insert(
+            "S".to_string(),
+            vec![Element::RawTerrain(RawTerrain::new(0, 0, Terrain::Swamp))],
+        );
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::SharedCell("S".to_string(), (0, 0))]
+        );
+    }
+
+    #[test]
+    fn should_report_portal_end_outside_map() {
+        // Given
+        let mut content = init_mock_content();
+        content.insert(
+            "P".to_string(),
+            vec![Element::RawPortal(RawPortal::new(2, 0, 7, 0))],
+        );
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::OutOfBounds("P".to_string(), (7, 0))]
+        );
+    }
+
+    #[test]
+    fn should_report_orientation_missing_from_grid() {
+        // Given
+        let mut content = init_mock_content();
+        content.insert("Grid".to_string(), vec![Element::RawGrid(Grid::Hex)]);
+        content.insert(
+            "A".to_string(),
+            vec![Element::RawAdventurer(RawAdventurer::new(
+                "Lara".to_string(),
+                0,
+                0,
+                Orientation::East,
+                "AA".to_string(),
+            ))],
+        );
+
+        // When + Then
+        assert_eq!(
+            validate(&content),
+            vec![ValidationError::InvalidOrientation(
+                "Lara".to_string(),
+                Orientation::East
+            )]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Golden-file tests: every `tests/scenarios/<name>/input.txt` is run through
+//! the engine and its result is compared with `expected.txt`.
+//!
+//! Run with `BLESS=1 cargo test --test scenarios` to regenerate the expected
+//! results after an intended change of behaviour.
+
+use std::{env, fs, fs::File, io, io::BufReader, path::Path};
+
+use treasure_map::{
+    file::{read_scenario, write_result},
+    simulation::Simulation,
+};
+
+const SCENARIOS_PATH: &str = "tests/scenarios";
+
+fn run_scenario(input: &Path) -> io::Result<String> {
+    let content = read_scenario(BufReader::new(File::open(input)?))?;
+    let mut simulation = Simulation::from_hashmap(&content);
+    simulation.run();
+
+    let mut output: Vec<u8> = Vec::new();
+    write_result(&simulation.to_hashmap(), &mut output)?;
+
+    String::from_utf8(output).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+#[test]
+fn should_match_expected_result_of_every_scenario() -> io::Result<()> {
+    let bless = env::var_os("BLESS").is_some();
+
+    let mut scenarios: Vec<_> = fs::read_dir(SCENARIOS_PATH)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    scenarios.sort();
+    assert!(
+        !scenarios.is_empty(),
+        "No scenario found in {}",
+        SCENARIOS_PATH
+    );
+
+    let mut failures = Vec::new();
+    for scenario in scenarios.iter() {
+        let actual = run_scenario(&scenario.join("input.txt"))?;
+        let expected_path = scenario.join("expected.txt");
+
+        if bless {
+            fs::write(&expected_path, &actual)?;
+            continue;
+        }
+
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{}\n--- expected\n{}+++ actual\n{}",
+                scenario.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} scenario(s) differ, run with BLESS=1 to accept:\n\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+
+    Ok(())
+}